@@ -0,0 +1,73 @@
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+
+/// Channel levels of the xterm 6x6x6 color cube (indices 16-231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Check whether the terminal advertises 24-bit color via $COLORTERM
+pub fn supports_truecolor() -> bool {
+    std::env::var("COLORTERM")
+        .map(|v| {
+            let v = v.to_lowercase();
+            v == "truecolor" || v == "24bit"
+        })
+        .unwrap_or(false)
+}
+
+/// Map an RGB color to the nearest xterm 256-color palette entry
+pub fn rgb_to_indexed(r: u8, g: u8, b: u8) -> u8 {
+    // Nearest color cube entry
+    let ri = nearest_cube_index(r);
+    let gi = nearest_cube_index(g);
+    let bi = nearest_cube_index(b);
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_idx = 16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8;
+
+    // Nearest grayscale ramp entry (232-255: 8, 18, ..., 238)
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_i = (avg.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_i;
+    let gray = (gray_level, gray_level, gray_level);
+
+    if distance((r, g, b), gray) < distance((r, g, b), cube) {
+        232 + gray_i
+    } else {
+        cube_idx
+    }
+}
+
+/// Convert a color for a terminal without truecolor (non-RGB colors pass through)
+pub fn downgrade(color: Color) -> Color {
+    match color {
+        Color::Rgb(r, g, b) => Color::Indexed(rgb_to_indexed(r, g, b)),
+        other => other,
+    }
+}
+
+/// Downgrade every RGB color in a rendered frame buffer to the 256-color palette
+pub fn downgrade_buffer(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        cell.fg = downgrade(cell.fg);
+        cell.bg = downgrade(cell.bg);
+    }
+}
+
+/// Check whether colors are turned off via a non-empty $NO_COLOR (no-color.org)
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+fn nearest_cube_index(v: u8) -> usize {
+    CUBE_LEVELS.iter()
+        .enumerate()
+        .min_by_key(|(_, level)| (**level as i32 - v as i32).abs())
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::Deserialize;
+
+use crate::keymap::Keymap;
+use crate::session::Usage;
+
+// Defaults
+const DEFAULT_THEME: &str = "rose-pine-moon";
+const DEFAULT_CLAUDE_BIN: &str = "claude";
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const DEFAULT_MAX_RENDERED_SESSIONS: usize = 100;
+const DEFAULT_CPU_THRESHOLD: f32 = 10.0;
+const DEFAULT_LOG_CACHE_SIZE: usize = 20;
+const DEFAULT_STALL_SECS: u64 = 300;
+const DEFAULT_SESSION_INTERVAL_MS: u64 = 2000;
+const DEFAULT_LOG_INTERVAL_MS: u64 = 500;
+const DEFAULT_EXCLUDE_JSONL_PREFIXES: &[&str] = &["agent-"];
+/// USD per million input/output tokens, by model id prefix
+const DEFAULT_PRICING: &[(&str, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-opus-4-5", 5.0, 25.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-haiku-4", 1.0, 5.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+];
+/// Floor for both refresh intervals, so a typo can't spin the CPU
+const MIN_INTERVAL_MS: u64 = 100;
+
+/// User configuration from ~/.config/claude-watch/config.toml
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Color theme name: rose-pine-moon, dark, or light
+    pub theme: String,
+    /// Historical sessions shown in the All view (0 = unlimited)
+    pub history_limit: usize,
+    /// Hard cap on sessions shown in the list (0 = unlimited)
+    pub max_rendered_sessions: usize,
+    /// CPU % above which a session counts as actively computing
+    pub cpu_threshold: f32,
+    /// Number of sessions whose parsed log is kept in memory
+    pub log_cache_size: usize,
+    /// Reuse the last known tmux location while tmux is restarting
+    pub remember_tmux_locations: bool,
+    /// Close a killed session's tmux window once its process is gone
+    pub kill_tmux_window: bool,
+    /// Include sub-agent (sidechain) sessions in the All view
+    pub show_sidechains: bool,
+    /// Start with the list grouped by repo (worktrees together)
+    pub group_by_repo: bool,
+    /// Claude executable used to resume/start sessions ($CLAUDE_BIN wins)
+    pub claude_bin: String,
+    /// Extra flags passed to claude when resuming or starting a session
+    pub claude_args: String,
+    /// tmux server socket (empty = the one in $TMUX; $CLAUDE_WATCH_TMUX_SOCKET wins)
+    pub tmux_socket: String,
+    /// Milliseconds between process scans (the heavy refresh)
+    pub session_interval_ms: u64,
+    /// Milliseconds between checks of the log file for changes
+    pub log_interval_ms: u64,
+    /// Milliseconds a tmux pane listing is reused across refreshes (0 = off)
+    pub pane_map_cache_ms: u64,
+    /// JSONL files whose names start with any of these aren't sessions
+    pub exclude_jsonl_prefixes: Vec<String>,
+    /// Heuristics behind the "needs me" badge
+    pub attention: AttentionConfig,
+    /// Token prices for the cost estimate, keyed by model id prefix
+    pub pricing: HashMap<String, ModelRate>,
+    /// `[keys]` overrides: action name -> key or keys (see keymap.rs)
+    pub keys: HashMap<String, KeyNames>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: DEFAULT_THEME.to_string(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            max_rendered_sessions: DEFAULT_MAX_RENDERED_SESSIONS,
+            cpu_threshold: DEFAULT_CPU_THRESHOLD,
+            log_cache_size: DEFAULT_LOG_CACHE_SIZE,
+            remember_tmux_locations: true,
+            kill_tmux_window: false,
+            show_sidechains: false,
+            group_by_repo: false,
+            claude_bin: DEFAULT_CLAUDE_BIN.to_string(),
+            claude_args: String::new(),
+            tmux_socket: String::new(),
+            session_interval_ms: DEFAULT_SESSION_INTERVAL_MS,
+            log_interval_ms: DEFAULT_LOG_INTERVAL_MS,
+            pane_map_cache_ms: 0,
+            exclude_jsonl_prefixes: DEFAULT_EXCLUDE_JSONL_PREFIXES.iter().map(|p| p.to_string()).collect(),
+            attention: AttentionConfig::default(),
+            pricing: DEFAULT_PRICING.iter()
+                .map(|&(model, input, output)| (model.to_string(), ModelRate::new(input, output)))
+                .collect(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Time between session refreshes, at least MIN_INTERVAL_MS
+    pub fn session_interval(&self) -> Duration {
+        Duration::from_millis(self.session_interval_ms.max(MIN_INTERVAL_MS))
+    }
+
+    /// Time between log refreshes, at least MIN_INTERVAL_MS
+    pub fn log_interval(&self) -> Duration {
+        Duration::from_millis(self.log_interval_ms.max(MIN_INTERVAL_MS))
+    }
+
+    /// Price of a model: the longest `pricing` key its id starts with
+    /// (so `claude-opus-4-5` beats `claude-opus-4`)
+    pub fn model_rate(&self, model: &str) -> Option<&ModelRate> {
+        self.pricing.iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, rate)| rate)
+    }
+}
+
+/// `[keys]` value: one key name or a list of them
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyNames {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyNames {
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            KeyNames::One(name) => std::slice::from_ref(name),
+            KeyNames::Many(names) => names,
+        }
+    }
+}
+
+/// `[pricing]` entry: USD per million tokens. Cache rates default to
+/// Anthropic's multiples of the input rate (reads 0.1x, writes 1.25x).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelRate {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+}
+
+impl ModelRate {
+    fn new(input: f64, output: f64) -> Self {
+        Self { input, output, cache_read: None, cache_write: None }
+    }
+
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let cache_read = self.cache_read.unwrap_or(self.input * 0.1);
+        let cache_write = self.cache_write.unwrap_or(self.input * 1.25);
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_read_input_tokens as f64 * cache_read
+            + usage.cache_creation_input_tokens as f64 * cache_write) / 1_000_000.0
+    }
+}
+
+/// `[attention]` section: which signals mark a session as needing you
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttentionConfig {
+    /// Session is waiting for input
+    pub waiting: bool,
+    /// Claude requested a tool that hasn't run (permission prompt)
+    pub pending_approval: bool,
+    /// Last tool result was an error
+    pub errors: bool,
+    /// Working session with no writes for this long counts as stalled (0 = off)
+    pub stall_secs: u64,
+    /// Sort sessions needing attention to the top of the list
+    pub float_to_top: bool,
+}
+
+impl Default for AttentionConfig {
+    fn default() -> Self {
+        Self {
+            waiting: true,
+            pending_approval: true,
+            errors: true,
+            stall_secs: DEFAULT_STALL_SECS,
+            float_to_top: false,
+        }
+    }
+}
+
+/// Path to the config file
+pub fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".config").join("claude-watch").join("config.toml"))
+}
+
+/// Load config from disk, falling back to defaults if missing or invalid
+pub fn load() -> Config {
+    let path = match config_path() {
+        Some(p) => p,
+        None => return with_env_overrides(Config::default()),
+    };
+
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(_) => return with_env_overrides(Config::default()),
+    };
+
+    let table = match content.parse::<toml::Table>() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("claude-watch: ignoring invalid config {}: {}", path.display(), e);
+            return with_env_overrides(Config::default());
+        }
+    };
+    let (table, problems) = valid_entries(table);
+    for problem in problems {
+        eprintln!("claude-watch: {}: {}", path.display(), problem);
+    }
+    let config: Config = toml::Value::Table(table).try_into().unwrap_or_default();
+    for problem in Keymap::new(&config.keys).1 {
+        eprintln!("claude-watch: {}: [keys] {}", path.display(), problem);
+    }
+    with_env_overrides(config)
+}
+
+/// Drop the keys `Config` rejects (unknown or of the wrong type), looking
+/// one level into sections so one bad `[attention]` key keeps the rest.
+/// Returns what's left and a message per dropped key.
+fn valid_entries(table: toml::Table) -> (toml::Table, Vec<String>) {
+    let accepts = |key: &str, value: toml::Value| -> Result<(), String> {
+        let single = toml::Table::from_iter([(key.to_string(), value)]);
+        toml::Value::Table(single).try_into::<Config>()
+            .map(|_| ())
+            .map_err(|e| e.message().to_string())
+    };
+    let describe = |name: &str, error: &str| {
+        if error.starts_with("unknown field") {
+            format!("ignoring unknown key `{}`", name)
+        } else {
+            format!("ignoring `{}`: {}", name, error)
+        }
+    };
+
+    let mut kept = toml::Table::new();
+    let mut problems = Vec::new();
+    for (key, value) in table {
+        let error = match accepts(&key, value.clone()) {
+            Ok(()) => {
+                kept.insert(key, value);
+                continue;
+            }
+            Err(e) => e,
+        };
+        let toml::Value::Table(section) = value else {
+            problems.push(describe(&key, &error));
+            continue;
+        };
+        // An unknown section fails as a whole; a known one, entry by entry
+        if accepts(&key, toml::Value::Table(toml::Table::new())).is_err() {
+            problems.push(describe(&key, &error));
+            continue;
+        }
+        let mut kept_section = toml::Table::new();
+        for (name, entry) in section {
+            let single = toml::Table::from_iter([(name.clone(), entry.clone())]);
+            match accepts(&key, toml::Value::Table(single)) {
+                Ok(()) => {
+                    kept_section.insert(name, entry);
+                }
+                Err(e) => problems.push(describe(&format!("{}.{}", key, name), &e)),
+            }
+        }
+        kept.insert(key, toml::Value::Table(kept_section));
+    }
+    (kept, problems)
+}
+
+/// Environment variables take precedence over the config file
+fn with_env_overrides(mut config: Config) -> Config {
+    if let Ok(bin) = std::env::var("CLAUDE_BIN") {
+        if !bin.is_empty() {
+            config.claude_bin = bin;
+        }
+    }
+    if let Ok(socket) = std::env::var("CLAUDE_WATCH_TMUX_SOCKET") {
+        if !socket.is_empty() {
+            config.tmux_socket = socket;
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> (Config, Vec<String>) {
+        let (table, problems) = valid_entries(content.parse().unwrap());
+        (toml::Value::Table(table).try_into().unwrap(), problems)
+    }
+
+    #[test]
+    fn unknown_and_mistyped_keys_are_dropped_and_named() {
+        let (config, problems) = parse(
+            "theme = \"light\"\nhistroy_limit = 5\ncpu_threshold = \"high\"\n",
+        );
+        assert_eq!(config.theme, "light");
+        assert_eq!(config.history_limit, DEFAULT_HISTORY_LIMIT);
+        assert_eq!(config.cpu_threshold, DEFAULT_CPU_THRESHOLD);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().any(|p| p == "ignoring unknown key `histroy_limit`"));
+        assert!(problems.iter().any(|p| p.starts_with("ignoring `cpu_threshold`")));
+    }
+
+    #[test]
+    fn longest_pricing_prefix_wins() {
+        let config = Config::default();
+        let rate = |model: &str| config.model_rate(model).map(|r| r.input);
+        assert_eq!(rate("claude-opus-4-1-20250805"), Some(15.0));
+        assert_eq!(rate("claude-opus-4-5-20251101"), Some(5.0));
+        assert_eq!(rate("gpt-4o"), None);
+        let usage = Usage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_read_input_tokens: 1_000_000,
+            cache_creation_input_tokens: 0,
+        };
+        let cost = ModelRate::new(3.0, 15.0).cost(&usage);
+        assert!((cost - 4.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bad_section_key_keeps_the_rest_of_the_section() {
+        let (config, problems) = parse("[attention]\nwaiting = false\nstall = 60\n[colors]\nbg = 1\n");
+        assert!(!config.attention.waiting);
+        assert_eq!(config.attention.stall_secs, DEFAULT_STALL_SECS);
+        assert_eq!(problems, vec![
+            "ignoring unknown key `attention.stall`".to_string(),
+            "ignoring unknown key `colors`".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::KeyNames;
+
+/// Something a key can do in the main view. Which pane the movement
+/// actions apply to depends on focus; see `App::perform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    /// `gg`: the key jumps the log to its newest message when pressed twice
+    LogNewest,
+    LogOldest,
+    ScrollLeft,
+    ScrollRight,
+    ToggleWrap,
+    CycleRoleFilter,
+    ToggleTools,
+    ToggleLogSource,
+    ReloadLog,
+    SearchLog,
+    Filter,
+    Command,
+    Help,
+    /// Esc: clear the log search, leave the log pane, or clear the filter, else quit
+    Back,
+    Quit,
+    Go,
+    ResumeHere,
+    NewSession,
+    Kill,
+    ForceKill,
+    Delete,
+    Pin,
+    CopyMessage,
+    CopyLogMessage,
+    Export,
+    OpenInEditor,
+    Refresh,
+    ToggleView,
+    ToggleFocus,
+    CycleSort,
+    BusyOnly,
+    WaitingOnly,
+    HideIdle,
+    GroupByRepo,
+    ToggleSidechains,
+    ToggleLastMessage,
+    ToggleCreatedTime,
+    ToggleCompact,
+    ToggleAttentionBadge,
+    NextAttention,
+    Pause,
+    Detail,
+    ProcessTree,
+    Diagnostics,
+}
+
+/// Every action with its `[keys]` name and default keys
+const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Down, "down", &["j", "Down"]),
+    (Action::Up, "up", &["k", "Up"]),
+    (Action::PageDown, "page_down", &["PageDown"]),
+    (Action::PageUp, "page_up", &["PageUp"]),
+    (Action::HalfPageDown, "half_page_down", &["C-d"]),
+    (Action::HalfPageUp, "half_page_up", &["C-u"]),
+    (Action::LogNewest, "log_newest", &["g"]),
+    (Action::LogOldest, "log_oldest", &["G"]),
+    (Action::ScrollLeft, "scroll_left", &["h", "Left"]),
+    (Action::ScrollRight, "scroll_right", &["l", "Right"]),
+    (Action::ToggleWrap, "toggle_wrap", &["w"]),
+    (Action::CycleRoleFilter, "cycle_role_filter", &["f"]),
+    (Action::ToggleTools, "toggle_tools", &["T"]),
+    (Action::ToggleLogSource, "toggle_log_source", &["L"]),
+    (Action::ReloadLog, "reload_log", &["C-r"]),
+    (Action::SearchLog, "search_log", &["C-f"]),
+    (Action::Filter, "filter", &["/"]),
+    (Action::Command, "command", &[":"]),
+    (Action::Help, "help", &["?"]),
+    (Action::Back, "back", &["Esc"]),
+    (Action::Quit, "quit", &["q"]),
+    (Action::Go, "go", &["Enter", "r"]),
+    (Action::ResumeHere, "resume_here", &["H"]),
+    (Action::NewSession, "new_session", &["n"]),
+    (Action::Kill, "kill", &["x"]),
+    (Action::ForceKill, "force_kill", &["X"]),
+    (Action::Delete, "delete", &["D", "d"]),
+    (Action::Pin, "pin", &["p"]),
+    (Action::CopyMessage, "copy_message", &["y"]),
+    (Action::CopyLogMessage, "copy_log_message", &["Y"]),
+    (Action::Export, "export", &["e"]),
+    (Action::OpenInEditor, "open_in_editor", &["o"]),
+    (Action::Refresh, "refresh", &["R"]),
+    (Action::ToggleView, "toggle_view", &["Tab"]),
+    (Action::ToggleFocus, "toggle_focus", &["BackTab"]),
+    (Action::CycleSort, "cycle_sort", &["s"]),
+    (Action::BusyOnly, "busy_only", &["c"]),
+    (Action::WaitingOnly, "waiting_only", &["W"]),
+    (Action::HideIdle, "hide_idle", &["I"]),
+    (Action::GroupByRepo, "group_by_repo", &["b"]),
+    (Action::ToggleSidechains, "toggle_sidechains", &["A"]),
+    (Action::ToggleLastMessage, "toggle_last_message", &["m"]),
+    (Action::ToggleCreatedTime, "toggle_created_time", &["C"]),
+    (Action::ToggleCompact, "toggle_compact", &["v"]),
+    (Action::ToggleAttentionBadge, "toggle_attention_badge", &["!"]),
+    (Action::NextAttention, "next_attention", &["a"]),
+    (Action::Pause, "pause", &["Space"]),
+    (Action::Detail, "detail", &["i"]),
+    (Action::ProcessTree, "process_tree", &["t"]),
+    (Action::Diagnostics, "diagnostics", &["F12"]),
+];
+
+/// A key press as the keymap sees it: Shift is already in the character,
+/// and Alt is ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key { code: event.code, ctrl: event.modifiers.contains(KeyModifiers::CONTROL) }
+    }
+}
+
+impl Key {
+    /// Parse `x`, `C-x` / `Ctrl-x`, or a name like `Enter`, `PageDown`, `F5`
+    pub fn parse(name: &str) -> Option<Key> {
+        let (ctrl, rest) = match name.strip_prefix("C-").or_else(|| name.strip_prefix("Ctrl-")) {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, name),
+        };
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            // Terminals report Ctrl-letters lowercase
+            (Some(c), None) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" | "s-tab" | "shift-tab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                f => KeyCode::F(f.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+            },
+        };
+        Some(Key { code, ctrl })
+    }
+
+    /// Short form for the help screens, e.g. `^D`, `PgDn`, `⇧Tab`
+    pub fn label(&self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) if self.ctrl => c.to_ascii_uppercase().to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "↵".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "⇧Tab".to_string(),
+            KeyCode::Backspace => "Bksp".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            _ => "?".to_string(),
+        };
+        if self.ctrl { format!("^{}", name) } else { name }
+    }
+}
+
+/// Keys of the main view and the actions they run: the defaults, with any
+/// action named in `[keys]` bound to the given keys instead
+pub struct Keymap {
+    actions: HashMap<Key, Action>,
+    /// Bindings in `ACTIONS` order, for listing an action's keys
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    /// Build the keymap, returning a message for each override that
+    /// names an unknown action or key (those are skipped)
+    pub fn new(overrides: &HashMap<String, KeyNames>) -> (Keymap, Vec<String>) {
+        let mut problems = Vec::new();
+        for name in overrides.keys() {
+            if !ACTIONS.iter().any(|(_, n, _)| n == name) {
+                problems.push(format!("unknown action `{}`", name));
+            }
+        }
+
+        // Overridden keys come off whatever action had them by default
+        let mut bindings: Vec<(Key, Action)> = Vec::new();
+        let mut taken: Vec<Key> = Vec::new();
+        for (action, name, _) in ACTIONS {
+            let keys: Vec<Key> = match overrides.get(*name) {
+                Some(names) => names.as_slice().iter()
+                    .filter_map(|k| {
+                        let key = Key::parse(k);
+                        if key.is_none() {
+                            problems.push(format!("unknown key `{}` for `{}`", k, name));
+                        }
+                        key
+                    })
+                    .collect(),
+                None => continue,
+            };
+            taken.extend(&keys);
+            bindings.extend(keys.into_iter().map(|k| (k, *action)));
+        }
+        for (action, name, defaults) in ACTIONS {
+            if overrides.contains_key(*name) {
+                continue;
+            }
+            for key in defaults.iter().filter_map(|k| Key::parse(k)) {
+                if !taken.contains(&key) {
+                    bindings.push((key, *action));
+                }
+            }
+        }
+        bindings.sort_by_key(|(_, action)| ACTIONS.iter().position(|(a, _, _)| a == action));
+
+        let actions = bindings.iter().copied().collect();
+        (Keymap { actions, bindings }, problems)
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+
+    /// The keys bound to `action`, e.g. `j/↓` (empty when unbound)
+    pub fn label(&self, action: Action) -> String {
+        self.bindings.iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(k, _)| key_label(k, action))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The first key bound to `action`, for the short help bar
+    pub fn first_label(&self, action: Action) -> Option<String> {
+        self.bindings.iter()
+            .find(|(_, a)| *a == action)
+            .map(|(k, _)| key_label(k, action))
+    }
+}
+
+/// A key's label, doubled for `LogNewest` since it takes two presses (`gg`)
+fn key_label(key: &Key, action: Action) -> String {
+    match action {
+        Action::LogNewest => key.label().repeat(2),
+        _ => key.label(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> Key {
+        Key::parse(name).unwrap()
+    }
+
+    #[test]
+    fn parses_chars_ctrl_and_named_keys() {
+        assert_eq!(key("x"), Key { code: KeyCode::Char('x'), ctrl: false });
+        assert_eq!(key("C-n"), Key { code: KeyCode::Char('n'), ctrl: true });
+        assert_eq!(key("Ctrl-p"), Key { code: KeyCode::Char('p'), ctrl: true });
+        assert_eq!(key("PageDown").code, KeyCode::PageDown);
+        assert_eq!(key("F12").code, KeyCode::F(12));
+        assert_eq!(key("-").code, KeyCode::Char('-'));
+        assert!(Key::parse("Hyper").is_none());
+        assert!(Key::parse("F13").is_none());
+    }
+
+    #[test]
+    fn every_default_key_parses() {
+        for (_, name, defaults) in ACTIONS {
+            for k in defaults.iter() {
+                assert!(Key::parse(k).is_some(), "{} default {}", name, k);
+            }
+        }
+    }
+
+    #[test]
+    fn overrides_replace_an_actions_keys_and_steal_others() {
+        let overrides = HashMap::from([
+            ("down".to_string(), KeyNames::Many(vec!["C-n".to_string(), "j".to_string()])),
+            ("kill".to_string(), KeyNames::One("K".to_string())),
+            // `h` was scroll_left's; it moves down now
+            ("up".to_string(), KeyNames::One("h".to_string())),
+            ("jump".to_string(), KeyNames::One("J".to_string())),
+        ]);
+        let (keymap, problems) = Keymap::new(&overrides);
+        assert_eq!(problems, vec!["unknown action `jump`".to_string()]);
+        assert_eq!(keymap.action(key("C-n")), Some(Action::Down));
+        assert_eq!(keymap.action(key("Down")), None);
+        assert_eq!(keymap.action(key("x")), None);
+        assert_eq!(keymap.action(key("K")), Some(Action::Kill));
+        assert_eq!(keymap.action(key("h")), Some(Action::Up));
+        assert_eq!(keymap.label(Action::ScrollLeft), "←");
+        assert_eq!(keymap.label(Action::Down), "^N/j");
+    }
+}
@@ -1,69 +1,282 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
+use chrono::{DateTime, Local, Utc};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Wrap};
+use unicode_width::UnicodeWidthChar;
 
-// Rose Pine Moon colors
-const GOLD: Color = Color::Rgb(246, 193, 119);
-const FOAM: Color = Color::Rgb(156, 207, 216);
-const SUBTLE: Color = Color::Rgb(110, 106, 134);
-const MUTED: Color = Color::Rgb(144, 140, 170);
-const TEXT: Color = Color::Rgb(224, 222, 244);
+use crate::session::{self, Session, SessionStatus};
+use crate::theme::Theme;
 
 const MAX_MESSAGES: usize = 50;
 const MAX_LINES_TO_SCAN: usize = 500;
+const TOOL_SUMMARY_LEN: usize = 80;
 
 #[derive(Debug, Clone)]
 pub struct LogMessage {
     pub role: String,
+    pub kind: LogKind,
+    /// Message text, or the one-line summary for tool entries
     pub content: String,
+    /// When the message was written, from the JSONL `timestamp` field
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Non-text blocks (images, documents) shown as placeholders
+    pub attachments: Vec<Attachment>,
 }
 
-/// Get the mtime of the most recent JSONL file for a project
-pub fn get_log_mtime(project_dir: &str) -> Option<SystemTime> {
-    let claude_dir = dirs::home_dir()?.join(".claude").join("projects");
-    let dir_name = convert_path_to_dir_name(project_dir);
-    let project_path = claude_dir.join(&dir_name);
-    let jsonl_path = find_most_recent_jsonl(&project_path)?;
-    fs::metadata(&jsonl_path).and_then(|m| m.modified()).ok()
+/// What a log entry represents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogKind {
+    /// Prose from the user or Claude
+    Text,
+    /// A tool call, e.g. `⚙ Bash: git status`
+    ToolUse,
+    /// A collapsed tool result, e.g. `↳ (ok, 12 lines)`
+    ToolResult,
 }
 
-/// Parse JSONL file and extract clean messages (user/assistant text only)
-pub fn parse_log_messages(project_dir: &str) -> Vec<LogMessage> {
-    let claude_dir = match dirs::home_dir() {
-        Some(h) => h.join(".claude").join("projects"),
-        None => return Vec::new(),
-    };
+/// Non-text content block the TUI can't display inline
+#[derive(Debug, Clone)]
+pub enum Attachment {
+    Image,
+    File(String),
+}
+
+impl Attachment {
+    pub fn label(&self) -> String {
+        match self {
+            Attachment::Image => "🖼 [image]".to_string(),
+            Attachment::File(name) => format!("📎 [file: {}]", name),
+        }
+    }
+}
 
-    // Convert project path to dir name
-    let dir_name = convert_path_to_dir_name(project_dir);
-    let project_path = claude_dir.join(&dir_name);
+/// How the log pane renders, from the App's toggles
+pub struct LogOptions<'a> {
+    /// Showing the project's newest JSONL rather than the session's own
+    pub follow_latest: bool,
+    /// Wrap long lines (otherwise truncate with `…` and scroll horizontally)
+    pub wrap: bool,
+    pub role_filter: RoleFilter,
+    /// Show tool call/result summaries between messages
+    pub show_tools: bool,
+    /// Log search query; matches are highlighted (empty = no search)
+    pub search: &'a str,
+    /// Keys act on the log pane (thick border, brighter when idle)
+    pub focused: bool,
+}
 
-    if !project_path.exists() {
-        return Vec::new();
+/// Which side of the conversation the log pane shows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoleFilter {
+    #[default]
+    All,
+    UserOnly,
+    AssistantOnly,
+}
+
+impl RoleFilter {
+    pub fn next(&self) -> Self {
+        match self {
+            RoleFilter::All => RoleFilter::UserOnly,
+            RoleFilter::UserOnly => RoleFilter::AssistantOnly,
+            RoleFilter::AssistantOnly => RoleFilter::All,
+        }
+    }
+
+    /// Role filters show conversation only, never tool entries
+    fn matches(&self, message: &LogMessage) -> bool {
+        if *self != RoleFilter::All && message.kind != LogKind::Text {
+            return false;
+        }
+        match self {
+            RoleFilter::All => true,
+            RoleFilter::UserOnly => message.role == "user",
+            RoleFilter::AssistantOnly => message.role == "assistant",
+        }
     }
 
-    // Find most recent JSONL file
-    let jsonl_path = match find_most_recent_jsonl(&project_path) {
-        Some(p) => p,
-        None => return Vec::new(),
+    fn label(&self) -> Option<&'static str> {
+        match self {
+            RoleFilter::All => None,
+            RoleFilter::UserOnly => Some("user"),
+            RoleFilter::AssistantOnly => Some("assistant"),
+        }
+    }
+}
+
+/// Scroll state of the log pane. Messages render newest first, so offset 0
+/// is the live tail and larger offsets move back through older content.
+#[derive(Default)]
+pub struct LogScroll {
+    pub offset: u16,
+    /// Largest useful offset, updated on each render
+    pub max_offset: Cell<u16>,
+    /// Visible rows, updated on each render
+    pub page_height: Cell<u16>,
+    /// Columns scrolled right in truncated mode
+    pub h_offset: u16,
+    /// Largest useful horizontal offset, updated on each render
+    pub max_h_offset: Cell<u16>,
+    /// Offsets that bring each line matching the search to the top,
+    /// newest first, updated on each render
+    pub match_offsets: RefCell<Vec<u16>>,
+}
+
+impl LogScroll {
+    /// Scroll toward older messages
+    pub fn down(&mut self, lines: u16) {
+        self.offset = self.offset.saturating_add(lines).min(self.max_offset.get());
+    }
+
+    /// Scroll toward newer messages (from where the last render clamped
+    /// the offset, which may be lower than asked for after a resize)
+    pub fn up(&mut self, lines: u16) {
+        self.offset = self.offset.min(self.max_offset.get()).saturating_sub(lines);
+    }
+
+    /// Jump to the newest message (resumes live tailing)
+    pub fn jump_newest(&mut self) {
+        self.offset = 0;
+    }
+
+    /// Jump to the oldest loaded message
+    pub fn jump_oldest(&mut self) {
+        self.offset = self.max_offset.get();
+    }
+
+    pub fn left(&mut self, cols: u16) {
+        self.h_offset = self.h_offset.min(self.max_h_offset.get()).saturating_sub(cols);
+    }
+
+    pub fn right(&mut self, cols: u16) {
+        self.h_offset = self.h_offset.saturating_add(cols).min(self.max_h_offset.get());
+    }
+
+    pub fn page(&self) -> u16 {
+        self.page_height.get().max(1)
+    }
+
+    pub fn half_page(&self) -> u16 {
+        (self.page_height.get() / 2).max(1)
+    }
+
+    /// Scroll so the `index`th search match (newest first) is at the top;
+    /// returns how many matches there are
+    pub fn jump_to_match(&mut self, index: usize) -> usize {
+        let offsets = self.match_offsets.borrow();
+        if let Some(&offset) = offsets.get(index) {
+            self.offset = offset.min(self.max_offset.get());
+        }
+        offsets.len()
+    }
+}
+
+/// LRU cache of parsed log messages, keyed by JSONL path. Growing files
+/// are parsed incrementally from where the previous parse stopped.
+pub struct LogCache {
+    capacity: usize,
+    entries: HashMap<String, CachedLog>,
+    /// Keys from least to most recently viewed
+    order: VecDeque<String>,
+}
+
+struct CachedLog {
+    mtime: Option<SystemTime>,
+    /// Byte offset just past the last complete line parsed
+    offset: u64,
+    messages: Vec<LogMessage>,
+}
+
+impl LogCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Messages for `path`: cached if unchanged since the last parse, extended
+    /// with just the appended lines if it grew, or parsed afresh otherwise
+    pub fn load(&mut self, path: &Path, mtime: Option<SystemTime>) -> Vec<LogMessage> {
+        let key = path.to_string_lossy().to_string();
+        let file_len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+        match self.entries.get_mut(&key) {
+            Some(entry) if entry.mtime == mtime => {}
+            Some(entry) if file_len >= entry.offset => {
+                let (new_messages, consumed) = parse_jsonl_from(path, entry.offset);
+                entry.messages.extend(new_messages);
+                trim_to_recent(&mut entry.messages);
+                entry.offset += consumed;
+                entry.mtime = mtime;
+            }
+            // New or truncated file: start over
+            _ => {
+                let (messages, offset) = parse_jsonl_from(path, 0);
+                self.insert(&key, CachedLog { mtime, offset, messages });
+            }
+        }
+
+        self.touch(&key);
+        self.entries.get(&key).map(|e| e.messages.clone()).unwrap_or_default()
+    }
+
+    /// Store an entry, evicting the least recently viewed entries beyond capacity
+    fn insert(&mut self, key: &str, entry: CachedLog) {
+        self.entries.insert(key.to_string(), entry);
+        self.touch(key);
+
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drop a file's cached parse so the next load reads it from scratch
+    pub fn forget(&mut self, path: &Path) {
+        let key = path.to_string_lossy().to_string();
+        self.entries.remove(&key);
+        self.order.retain(|k| *k != key);
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.order.retain(|k| k != key);
+        self.order.push_back(key.to_string());
+    }
+}
+
+/// Resolve the JSONL file the log pane shows for a session: the file that
+/// produced its status, or the project's most recent file when `latest` is set
+pub fn resolve_log_path(session: &Session, latest: bool) -> Option<PathBuf> {
+    if !latest {
+        if let Some(ref path) = session.jsonl_path {
+            return Some(PathBuf::from(path));
+        }
+    }
+    let claude_dir = match session.projects_root {
+        Some(ref root) => PathBuf::from(root),
+        None => session::projects_dirs().into_iter().next()?,
     };
+    let dir_name = convert_path_to_dir_name(&session.project_path);
+    find_most_recent_jsonl(&claude_dir.join(&dir_name))
+}
 
-    // Read and parse messages
-    parse_jsonl_messages(&jsonl_path)
+/// Get the mtime of a JSONL file
+pub fn get_log_mtime(jsonl_path: &Path) -> Option<SystemTime> {
+    fs::metadata(jsonl_path).and_then(|m| m.modified()).ok()
 }
 
 fn find_most_recent_jsonl(project_dir: &PathBuf) -> Option<PathBuf> {
     std::fs::read_dir(project_dir).ok()?
         .flatten()
-        .filter(|e| {
-            let path = e.path();
-            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            path.extension().map(|ext| ext == "jsonl").unwrap_or(false)
-                && !name.starts_with("agent-")
-        })
+        .filter(|e| session::is_session_jsonl(&e.path()))
         .filter_map(|e| {
             let modified = e.metadata().and_then(|m| m.modified()).ok()?;
             Some((e.path(), modified))
@@ -72,56 +285,188 @@ fn find_most_recent_jsonl(project_dir: &PathBuf) -> Option<PathBuf> {
         .map(|(path, _)| path)
 }
 
-fn parse_jsonl_messages(path: &PathBuf) -> Vec<LogMessage> {
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => return Vec::new(),
-    };
+/// Parse the complete lines after byte `offset` (only the last N of them).
+/// Returns the messages and how many bytes were consumed; a partially
+/// written final line is left for the next call.
+fn parse_jsonl_from(path: &Path, offset: u64) -> (Vec<LogMessage>, u64) {
+    let mut bytes = Vec::new();
+    let read = File::open(path).and_then(|mut file| {
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_to_end(&mut bytes)
+    });
+    if read.is_err() {
+        return (Vec::new(), 0);
+    }
 
-    let reader = BufReader::new(file);
-    let lines: Vec<String> = reader.lines().flatten().collect();
+    let complete = bytes.iter().rposition(|&b| b == b'\n').map(|i| i + 1).unwrap_or(0);
+    let lines: Vec<&[u8]> = bytes[..complete]
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .collect();
 
     // Take last N lines for efficiency
     let start = lines.len().saturating_sub(MAX_LINES_TO_SCAN);
     let mut messages = Vec::new();
 
-    for line in lines.into_iter().skip(start) {
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-            if let Some(msg) = extract_message(&json) {
-                messages.push(msg);
+    for line in &lines[start..] {
+        if let Ok(json) = serde_json::from_slice::<serde_json::Value>(line) {
+            messages.extend(extract_messages(&json));
+        }
+    }
+
+    trim_to_recent(&mut messages);
+    (messages, complete as u64)
+}
+
+/// Write a session's whole conversation to `out` as Markdown: a heading per
+/// turn with its time, the text, and tool calls/results as quoted summaries.
+/// The JSONL is streamed line by line, so huge sessions aren't held in memory.
+/// Returns the number of entries written.
+pub fn export_markdown(jsonl_path: &Path, out: &Path, title: &str) -> io::Result<usize> {
+    let reader = BufReader::new(File::open(jsonl_path)?);
+    let mut writer = BufWriter::new(File::create(out)?);
+    writeln!(writer, "# {}\n", title)?;
+
+    let mut count = 0;
+    let mut last_role = String::new();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let Ok(json) = serde_json::from_slice::<serde_json::Value>(&line) else { continue };
+        for message in extract_messages(&json) {
+            // New heading whenever the speaker changes (tool results are
+            // user-role lines but belong to the assistant's turn)
+            if message.kind != LogKind::ToolResult && message.role != last_role {
+                let role = if message.role == "user" { "User" } else { "Assistant" };
+                match message.timestamp {
+                    Some(t) => writeln!(writer, "## {} · {}\n", role, t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"))?,
+                    None => writeln!(writer, "## {}\n", role)?,
+                }
+                last_role = message.role.clone();
             }
+            match message.kind {
+                LogKind::Text => {
+                    if !message.content.trim().is_empty() {
+                        writeln!(writer, "{}\n", message.content.trim_end())?;
+                    }
+                    for attachment in &message.attachments {
+                        writeln!(writer, "_{}_\n", attachment.label())?;
+                    }
+                }
+                LogKind::ToolUse | LogKind::ToolResult => writeln!(writer, "> {}\n", message.content)?,
+            }
+            count += 1;
         }
     }
+    writer.flush()?;
+    Ok(count)
+}
 
-    // Keep only recent messages
+/// Keep only the most recent MAX_MESSAGES
+fn trim_to_recent(messages: &mut Vec<LogMessage>) {
     if messages.len() > MAX_MESSAGES {
         messages.drain(0..messages.len() - MAX_MESSAGES);
     }
-
-    messages
 }
 
-fn extract_message(json: &serde_json::Value) -> Option<LogMessage> {
-    let message = json.get("message")?;
-    let role = message.get("role")?.as_str()?;
+/// Split a JSONL line into log entries: its text (with attachments),
+/// then one summary per tool_use/tool_result block
+fn extract_messages(json: &serde_json::Value) -> Vec<LogMessage> {
+    let Some(message) = json.get("message") else { return Vec::new() };
+    let role = match message.get("role").and_then(|r| r.as_str()) {
+        Some(r @ ("user" | "assistant")) => r,
+        // Only include user and assistant messages
+        _ => return Vec::new(),
+    };
+    let Some(content) = message.get("content") else { return Vec::new() };
 
-    // Only include user and assistant messages
-    if role != "user" && role != "assistant" {
-        return None;
+    let timestamp = json.get("timestamp")
+        .and_then(|t| t.as_str())
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc));
+    let entry = |kind, content| LogMessage {
+        role: role.to_string(),
+        kind,
+        content,
+        timestamp,
+        attachments: Vec::new(),
+    };
+
+    let mut messages = Vec::new();
+
+    let text = extract_text_content(content).unwrap_or_default();
+    let attachments = extract_attachments(content);
+    // Skip empty text (tool-only lines still get their summaries below)
+    if !text.trim().is_empty() || !attachments.is_empty() {
+        messages.push(LogMessage { attachments, ..entry(LogKind::Text, text) });
     }
 
-    let content = message.get("content")?;
-    let text = extract_text_content(content)?;
+    if let serde_json::Value::Array(blocks) = content {
+        for block in blocks {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("tool_use") => messages.push(entry(LogKind::ToolUse, summarize_tool_use(block))),
+                Some("tool_result") => messages.push(entry(LogKind::ToolResult, summarize_tool_result(block))),
+                _ => {}
+            }
+        }
+    }
+
+    messages
+}
 
-    // Skip empty or tool-only messages
-    if text.trim().is_empty() {
-        return None;
+/// `⚙ Name: <main argument>`, e.g. the command for Bash or the path for Edit
+fn summarize_tool_use(block: &serde_json::Value) -> String {
+    let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("tool");
+    let input = block.get("input");
+    let arg = ["command", "file_path", "path", "pattern", "url", "query", "description"]
+        .iter()
+        .find_map(|key| input?.get(key)?.as_str())
+        .or_else(|| input?.as_object()?.values().find_map(|v| v.as_str()));
+
+    match arg.and_then(|a| a.lines().next()) {
+        Some(arg) => {
+            let arg = if arg.chars().count() > TOOL_SUMMARY_LEN {
+                format!("{}…", arg.chars().take(TOOL_SUMMARY_LEN - 1).collect::<String>())
+            } else {
+                arg.to_string()
+            };
+            format!("⚙ {}: {}", name, arg)
+        }
+        None => format!("⚙ {}", name),
     }
+}
 
-    Some(LogMessage {
-        role: role.to_string(),
-        content: text,
-    })
+/// `↳ (ok, 12 lines)` / `↳ (error, 3 lines)`
+fn summarize_tool_result(block: &serde_json::Value) -> String {
+    let is_error = block.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false);
+    let lines = block.get("content")
+        .and_then(extract_text_content)
+        .map(|t| t.lines().count())
+        .unwrap_or(0);
+    let outcome = if is_error { "error" } else { "ok" };
+    let noun = if lines == 1 { "line" } else { "lines" };
+    format!("↳ ({}, {} {})", outcome, lines, noun)
+}
+
+/// Collect image/document blocks so they aren't silently dropped
+fn extract_attachments(content: &serde_json::Value) -> Vec<Attachment> {
+    let arr = match content {
+        serde_json::Value::Array(arr) => arr,
+        _ => return Vec::new(),
+    };
+
+    arr.iter()
+        .filter_map(|item| match item.get("type")?.as_str()? {
+            "image" => Some(Attachment::Image),
+            "document" => {
+                let name = item.get("title")
+                    .or_else(|| item.get("source").and_then(|s| s.get("media_type")))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("document");
+                Some(Attachment::File(name.to_string()))
+            }
+            _ => None,
+        })
+        .collect()
 }
 
 fn extract_text_content(content: &serde_json::Value) -> Option<String> {
@@ -170,48 +515,289 @@ fn convert_path_to_dir_name(path: &str) -> String {
     result
 }
 
-/// Render the log view panel
-pub fn render_log(frame: &mut Frame, area: Rect, messages: &[LogMessage]) {
+/// Render the log view panel, its border tinted by `status` (the selected
+/// session's, when it's running)
+pub fn render_log(
+    frame: &mut Frame,
+    theme: &Theme,
+    area: Rect,
+    messages: &[LogMessage],
+    options: &LogOptions,
+    scroll: &LogScroll,
+    status: Option<&SessionStatus>,
+) {
+    let mut tags: Vec<String> = Vec::new();
+    if options.follow_latest {
+        tags.push("latest".to_string());
+    }
+    if let Some(role) = options.role_filter.label() {
+        tags.push(role.to_string());
+    }
+    if !options.search.is_empty() {
+        tags.push(format!("/{}", options.search));
+    }
+    let title = if tags.is_empty() {
+        " Log ".to_string()
+    } else {
+        format!(" Log ({}) ", tags.join(", "))
+    };
+    let border_style = match status {
+        Some(SessionStatus::Thinking) => theme.accent,
+        Some(SessionStatus::Processing) => theme.working,
+        Some(SessionStatus::Waiting) => theme.info,
+        Some(SessionStatus::Idle) | None if options.focused => theme.highlight,
+        Some(SessionStatus::Idle) | None => theme.subtle,
+    };
     let block = Block::default()
-        .title(" Log ")
-        .title_style(Style::default().fg(GOLD))
+        .title(title)
+        .title_style(theme.accent)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(SUBTLE));
+        .border_type(if options.focused { BorderType::Thick } else { BorderType::Plain })
+        .border_style(border_style);
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    let messages: Vec<&LogMessage> = messages.iter()
+        .filter(|m| options.role_filter.matches(m))
+        .filter(|m| options.show_tools || m.kind == LogKind::Text)
+        .collect();
+
     if messages.is_empty() {
+        scroll.max_offset.set(0);
+        scroll.match_offsets.borrow_mut().clear();
         let empty = Paragraph::new("No messages yet")
-            .style(Style::default().fg(MUTED))
+            .style(theme.muted)
             .alignment(Alignment::Center);
         frame.render_widget(empty, inner);
         return;
     }
 
+    // Time column: only shown on the first message of each minute
+    let show_times = messages.iter().any(|m| m.timestamp.is_some());
+    let minute = |m: &LogMessage| m.timestamp.map(|t| t.timestamp() / 60);
+    let time_labels: Vec<Option<String>> = messages.iter()
+        .enumerate()
+        .map(|(i, msg)| {
+            let ts = msg.timestamp?;
+            if i > 0 && minute(messages[i - 1]) == minute(msg) {
+                return None;
+            }
+            Some(ts.with_timezone(&Local).format("%H:%M:%S ").to_string())
+        })
+        .collect();
+    let time_blank = if show_times { " ".repeat(9) } else { String::new() };
+
     // Build text with role prefixes - newest first (reverse order)
     let mut lines: Vec<Line> = Vec::new();
+    // Indices into `lines` of lines with a search match
+    let mut matching: Vec<usize> = Vec::new();
+    let match_style = theme.highlight.reversed();
+    let mut content_spans = |text: &str, style: Style, line_index: usize| {
+        let ranges = match_ranges(text, options.search);
+        if !ranges.is_empty() {
+            matching.push(line_index);
+        }
+        highlight(text, &ranges, style, match_style)
+    };
 
-    for msg in messages.iter().rev() {
-        let (prefix, color) = match msg.role.as_str() {
-            "user" => ("› ", FOAM),
-            "assistant" => ("  ", TEXT),
-            _ => ("  ", MUTED),
+    for (msg, time_label) in messages.iter().zip(time_labels).rev() {
+        let (prefix, prefix_style) = match msg.role.as_str() {
+            "user" => ("› ", theme.info),
+            "assistant" => ("  ", theme.text),
+            _ => ("  ", theme.muted),
         };
+        let time_style = theme.subtle;
+        let mut time_label = Some(time_label.unwrap_or_else(|| time_blank.clone()));
+
+        // Tool summaries: one dimmed line, kept tight against their message
+        if msg.kind != LogKind::Text {
+            let mut spans = vec![
+                Span::styled(time_label.take().unwrap_or_default(), time_style),
+                Span::styled("  ", Style::default()),
+            ];
+            spans.extend(content_spans(&msg.content, theme.subtle, lines.len()));
+            lines.push(Line::from(spans));
+            continue;
+        }
 
         // Wrap long messages
         for (i, line) in msg.content.lines().enumerate() {
             let line_prefix = if i == 0 { prefix } else { "  " };
+            let mut spans = vec![
+                Span::styled(time_label.take().unwrap_or_else(|| time_blank.clone()), time_style),
+                Span::styled(line_prefix, prefix_style),
+            ];
+            let text_style = if msg.role == "user" { prefix_style } else { theme.text };
+            spans.extend(content_spans(line, text_style, lines.len()));
+            lines.push(Line::from(spans));
+        }
+
+        // Attachment placeholders (prefix goes here if there was no text)
+        let has_text = msg.content.lines().next().is_some();
+        for (i, attachment) in msg.attachments.iter().enumerate() {
+            let line_prefix = if i == 0 && !has_text { prefix } else { "  " };
             lines.push(Line::from(vec![
-                Span::styled(line_prefix, Style::default().fg(color)),
-                Span::styled(line.to_string(), Style::default().fg(if msg.role == "user" { color } else { TEXT })),
+                Span::styled(time_label.take().unwrap_or_else(|| time_blank.clone()), time_style),
+                Span::styled(line_prefix, prefix_style),
+                Span::styled(attachment.label(), theme.muted),
             ]));
         }
         lines.push(Line::from("")); // Spacing between messages
     }
 
-    let paragraph = Paragraph::new(lines)
-        .wrap(Wrap { trim: false });
+    // Row each matching line starts on: its index, or with wrapping, the
+    // rows taken by the lines above it
+    let match_offsets: Vec<u16> = if options.wrap {
+        let mut offsets = Vec::with_capacity(matching.len());
+        let mut row = 0;
+        let mut next = matching.iter().peekable();
+        for (i, line) in lines.iter().enumerate() {
+            if next.peek() == Some(&&i) {
+                offsets.push(row.min(u16::MAX as usize) as u16);
+                next.next();
+            }
+            row += Paragraph::new(line.clone()).wrap(Wrap { trim: false }).line_count(inner.width);
+        }
+        offsets
+    } else {
+        matching.iter().map(|&i| i.min(u16::MAX as usize) as u16).collect()
+    };
+    *scroll.match_offsets.borrow_mut() = match_offsets;
+
+    let paragraph = if options.wrap {
+        scroll.max_h_offset.set(0);
+        Paragraph::new(lines).wrap(Wrap { trim: false })
+    } else {
+        let width = inner.width as usize;
+        let longest = lines.iter().map(|l| l.width()).max().unwrap_or(0);
+        let max_h_offset = longest.saturating_sub(width).min(u16::MAX as usize) as u16;
+        scroll.max_h_offset.set(max_h_offset);
+        let h_offset = scroll.h_offset.min(max_h_offset) as usize;
+        let ellipsis = theme.muted;
+        let clipped: Vec<Line> = lines.into_iter()
+            .map(|l| clip_line(l, h_offset, width, ellipsis))
+            .collect();
+        Paragraph::new(clipped)
+    };
+
+    // Record scroll bounds for the key handlers, then clamp to them
+    let max_offset = paragraph.line_count(inner.width)
+        .saturating_sub(inner.height as usize)
+        .min(u16::MAX as usize) as u16;
+    scroll.max_offset.set(max_offset);
+    scroll.page_height.set(inner.height);
 
+    let paragraph = paragraph.scroll((scroll.offset.min(max_offset), 0));
     frame.render_widget(paragraph, inner);
 }
+
+/// Byte ranges of case-insensitive occurrences of `query` in `text`
+fn match_ranges(text: &str, query: &str) -> Vec<(usize, usize)> {
+    let needle: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        // Compare lowercased chars from `start` until the needle is used up
+        let (mut matched, mut end) = (0, start);
+        while end < chars.len() && matched < needle.len() {
+            let lower: Vec<char> = chars[end].1.to_lowercase().collect();
+            if !needle[matched..].starts_with(&lower) {
+                break;
+            }
+            matched += lower.len();
+            end += 1;
+        }
+        if matched == needle.len() {
+            let end_byte = chars.get(end).map(|&(b, _)| b).unwrap_or(text.len());
+            ranges.push((chars[start].0, end_byte));
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+    ranges
+}
+
+/// Split `text` into spans, styling the given byte ranges as matches
+fn highlight(text: &str, ranges: &[(usize, usize)], style: Style, match_style: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for &(start, end) in ranges {
+        if start > pos {
+            spans.push(Span::styled(text[pos..start].to_string(), style));
+        }
+        spans.push(Span::styled(text[start..end].to_string(), match_style));
+        pos = end;
+    }
+    if pos < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[pos..].to_string(), style));
+    }
+    spans
+}
+
+/// Cut a line to `width` columns starting `h_offset` columns in, marking
+/// cut-off text on the right with `…`. Columns are display columns, so a
+/// wide character (CJK, emoji) counts as two; one split by either edge is
+/// replaced by spaces to keep the rest aligned.
+fn clip_line(line: Line<'static>, h_offset: usize, width: usize, ellipsis_style: Style) -> Line<'static> {
+    let overflows = line.width() > h_offset + width;
+    let mut column = 0;
+    let mut remaining = if overflows { width.saturating_sub(1) } else { width };
+    let mut spans = Vec::new();
+
+    for span in line.spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let start = column;
+            column += c.width().unwrap_or(0);
+            if column <= h_offset {
+                continue;
+            }
+            let visible = column - start.max(h_offset);
+            if start < h_offset || visible > remaining {
+                let pad = visible.min(remaining);
+                text.extend(std::iter::repeat_n(' ', pad));
+                remaining -= pad;
+            } else {
+                text.push(c);
+                remaining -= visible;
+            }
+        }
+        spans.push(Span::styled(text, span.style));
+    }
+    if overflows {
+        spans.push(Span::styled("…", ellipsis_style));
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_ranges_ignores_case_and_keeps_char_boundaries() {
+        assert_eq!(match_ranges("Error: error", "ERROR"), [(0, 5), (7, 12)]);
+        assert_eq!(match_ranges("naïve NAÏVE", "naïve"), [(0, 6), (7, 13)]);
+        assert_eq!(match_ranges("aaa", "aa"), [(0, 2)]);
+        assert!(match_ranges("anything", "").is_empty());
+    }
+
+    #[test]
+    fn clip_line_counts_wide_characters_as_two_columns() {
+        let clip = |text: &'static str, h_offset, width| {
+            clip_line(Line::from(text), h_offset, width, Style::default()).to_string()
+        };
+        assert_eq!(clip("日本語テキスト", 4, 7), "語テキ…");
+        // A wide character split by either edge becomes a space
+        assert_eq!(clip("日本語テキスト", 0, 6), "日本 …");
+        assert_eq!(clip("日本語テキスト", 3, 6), " 語テ…");
+        assert_eq!(clip("ab日本", 0, 6), "ab日本");
+    }
+}
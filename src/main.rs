@@ -1,22 +1,41 @@
+mod color;
+mod config;
+mod keymap;
 mod process;
 mod session;
+mod theme;
 mod tmux;
 mod ui;
 mod log_view;
+mod mux;
+mod state;
+mod zellij;
 
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
-use std::time::{Duration, SystemTime};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::execute;
 use ratatui::prelude::*;
 use ratatui::Terminal;
+use serde::{Deserialize, Serialize};
 
-use session::Session;
-use log_view::LogMessage;
+use config::Config;
+use keymap::{Action, Key, Keymap};
+use session::{Session, SessionStatus};
+use log_view::{LogCache, LogKind, LogMessage, LogScroll, RoleFilter};
+use mux::Multiplexer;
+use process::ProcessAncestor;
+use theme::Theme;
+use tmux::TmuxLocation;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum ViewMode {
+    #[default]
     Running,
     All,
 }
@@ -37,136 +56,1613 @@ impl ViewMode {
     }
 }
 
+/// Pane that j/k, PageUp/PageDown, and `/` act on, switched with Shift-Tab
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Focus {
+    #[default]
+    Sessions,
+    Log,
+}
+
+/// Order of the session list, cycled with `s`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum SortMode {
+    /// Running by tmux target, then historical by recency (fetch order)
+    #[default]
+    Tmux,
+    Activity,
+    Status,
+    Name,
+}
+
+impl SortMode {
+    fn next(&self) -> Self {
+        match self {
+            SortMode::Tmux => SortMode::Activity,
+            SortMode::Activity => SortMode::Status,
+            SortMode::Status => SortMode::Name,
+            SortMode::Name => SortMode::Tmux,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SortMode::Tmux => "by tmux",
+            SortMode::Activity => "by activity",
+            SortMode::Status => "by status",
+            SortMode::Name => "by name",
+        }
+    }
+
+    /// Stable-sort sessions in place (Tmux keeps the fetch order)
+    fn sort(&self, sessions: &mut [Session]) {
+        match self {
+            SortMode::Tmux => {}
+            SortMode::Activity => sessions.sort_by_key(|s| s.last_activity_secs),
+            SortMode::Status => sessions.sort_by_key(status_rank),
+            SortMode::Name => sessions.sort_by_key(|s| s.project_name.to_lowercase()),
+        }
+    }
+}
+
+/// A command typed at the `:` prompt
+enum PromptCommand {
+    Kill { force: bool },
+    Delete,
+    Resume { here: bool },
+    New,
+    Sort(SortMode),
+    Filter(String),
+    View(ViewMode),
+    Refresh,
+    Pause,
+    Quit,
+}
+
+impl PromptCommand {
+    /// Parse e.g. `kill`, `kill!`, `sort activity`, `filter foo bar`, `view all`
+    fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (name, arg) = input.split_once(' ').map_or((input, ""), |(n, a)| (n, a.trim()));
+        let command = match (name, arg) {
+            ("kill", "") => PromptCommand::Kill { force: false },
+            ("kill!", "") => PromptCommand::Kill { force: true },
+            ("delete", "") => PromptCommand::Delete,
+            ("resume" | "go", "") => PromptCommand::Resume { here: false },
+            ("resume", "here") => PromptCommand::Resume { here: true },
+            ("new", "") => PromptCommand::New,
+            ("sort", "tmux") => PromptCommand::Sort(SortMode::Tmux),
+            ("sort", "activity") => PromptCommand::Sort(SortMode::Activity),
+            ("sort", "status") => PromptCommand::Sort(SortMode::Status),
+            ("sort", "name") => PromptCommand::Sort(SortMode::Name),
+            ("sort", _) => return Err("sort: expected tmux, activity, status, or name".to_string()),
+            ("filter", query) => PromptCommand::Filter(query.to_string()),
+            ("view", "running") => PromptCommand::View(ViewMode::Running),
+            ("view", "all") => PromptCommand::View(ViewMode::All),
+            ("view", _) => return Err("view: expected running or all".to_string()),
+            ("refresh", "") => PromptCommand::Refresh,
+            ("pause", "") => PromptCommand::Pause,
+            ("q" | "quit", "") => PromptCommand::Quit,
+            _ => return Err(format!("unknown command: {}", input)),
+        };
+        Ok(command)
+    }
+}
+
+/// Busy sessions first, historical last
+fn status_rank(session: &Session) -> u8 {
+    if !session.is_running {
+        return 3;
+    }
+    match session.status {
+        SessionStatus::Thinking | SessionStatus::Processing => 0,
+        SessionStatus::Waiting => 1,
+        SessionStatus::Idle => 2,
+    }
+}
+
+/// Columns moved per h/l in the truncated log view
+const LOG_H_SCROLL_STEP: u16 = 8;
+
+/// How long a SIGTERM'd session gets before it is SIGKILL'd
+const KILL_ESCALATE_AFTER: Duration = Duration::from_secs(2);
+/// The same for a force kill (`X`)
+const FORCE_KILL_ESCALATE_AFTER: Duration = Duration::from_millis(500);
+
+/// CPU samples kept per running session for the detail sparkline
+const CPU_HISTORY_LEN: usize = 20;
+
+/// A killed session, checked on each refresh until its process exits
+struct PendingKill {
+    pid: u32,
+    session_id: String,
+    sent_at: Instant,
+    /// SIGKILL is sent if the process outlives this
+    escalate_after: Duration,
+    /// SIGKILL already sent (by escalation, or by `X` when SIGTERM couldn't be)
+    forced: bool,
+    /// Window to close once the process is gone (with `kill_tmux_window`)
+    tmux_location: Option<TmuxLocation>,
+}
+
+/// Destructive action awaiting confirmation
+#[derive(Clone, Copy)]
+enum ConfirmAction {
+    Kill,
+    ForceKill,
+    Delete,
+}
+
+/// Modal drawn over the main layout; captures all keys while open
+enum Overlay {
+    /// y/n prompt before a destructive action on the session with this id
+    Confirm {
+        action: ConfirmAction,
+        session_id: String,
+        message: String,
+    },
+    /// Process ancestry of the selected session, Claude first
+    ProcessTree {
+        ancestors: Vec<ProcessAncestor>,
+        tmux_target: Option<String>,
+    },
+    /// Full metadata of the session with this id
+    Detail { session_id: String },
+    /// Every keybinding, grouped by category
+    Help,
+}
+
 struct App {
+    config: Config,
+    theme: Theme,
+    /// Main-view keys: defaults plus the config's `[keys]` overrides
+    keymap: Keymap,
+    /// Sessions from the last refresh, before filters
+    fetched_sessions: Vec<Session>,
+    /// Visible sessions (filtered and capped)
     sessions: Vec<Session>,
+    /// Session count before the max_rendered_sessions cap
+    total_sessions: usize,
     selected: usize,
     should_quit: bool,
     log_messages: Vec<LogMessage>,
     last_log_mtime: Option<SystemTime>,
+    /// Parsed logs of recently viewed sessions
+    log_cache: LogCache,
+    log_scroll: LogScroll,
+    /// Log scroll offset of each session scrolled away from the live tail
+    log_offsets: HashMap<String, u16>,
+    /// Wrap long log lines (otherwise truncate and scroll with h/l)
+    log_wrap: bool,
+    log_role_filter: RoleFilter,
+    /// Show tool call/result summaries in the log
+    log_show_tools: bool,
+    /// First `g` of a `gg` sequence was pressed
+    pending_g: bool,
+    /// Digits typed so far for a numeric session jump
+    pending_digits: String,
     view_mode: ViewMode,
+    /// Show last message instead of first prompt on historical cards
+    show_last_message: bool,
+    /// Show when historical sessions were created instead of last active
+    show_created_time: bool,
+    /// Terminal supports 24-bit color (otherwise fall back to 256 colors)
+    truecolor: bool,
+    /// Only show sessions using CPU above the configured threshold
+    busy_only: bool,
+    /// Only show running sessions waiting for input
+    waiting_only: bool,
+    /// Hide running sessions that are idle (historical ones stay listed)
+    hide_idle: bool,
+    /// Cluster the list by repo, worktrees together, under group headers
+    group_by_repo: bool,
+    /// Git common dir per listed project path (None outside a repo),
+    /// updated on refresh while grouping by repo
+    repo_dirs: HashMap<String, Option<String>>,
+    /// Log pane follows the project's most recent JSONL instead of the session's own
+    log_follow_latest: bool,
+    /// Render the "needs me" badge on cards
+    show_attention: bool,
+    /// Case-insensitive filter on project name and last message
+    filter_query: String,
+    /// Typing into the filter query
+    filter_input: bool,
+    /// Text typed at the `:` prompt (Some while it's open)
+    command_input: Option<String>,
+    overlay: Option<Overlay>,
+    /// Last tmux location seen per running session id
+    known_tmux_locations: HashMap<String, TmuxLocation>,
+    /// Sessions sent SIGTERM that haven't exited yet
+    pending_kills: Vec<PendingKill>,
+    /// Opened on first copy and kept alive so X11/Wayland keep serving the contents
+    clipboard: Option<arboard::Clipboard>,
+    /// Status message shown in the help bar for one render
+    flash: Option<String>,
+    /// Multiplexer the sessions run in, detected at startup
+    mux: Box<dyn Multiplexer>,
+    /// Claude command to print after quitting when no window could be opened for it
+    exit_command: Option<String>,
+    /// Resume command `H` asked to run in the current pane after quitting
+    here_command: Option<String>,
+    /// File `o` asked to open in $EDITOR; the main loop suspends the TUI for it
+    edit_path: Option<PathBuf>,
+    /// Skip the automatic session and log refreshes (R still works)
+    paused: bool,
+    /// --read-only: kill, delete, switch, resume, and new session are no-ops
+    read_only: bool,
+    sort_mode: SortMode,
+    /// When each running session entered its current Thinking/Processing streak
+    busy_since: HashMap<String, Instant>,
+    /// Recent CPU % of each running session, one sample per refresh, oldest first
+    cpu_history: HashMap<String, VecDeque<f32>>,
+    /// Include sub-agent sessions in the All view
+    show_sidechains: bool,
+    /// Ids of sessions pinned with `p`, sorted above the rest
+    pinned: HashSet<String>,
+    /// --debug: F12 toggles the diagnostics pane
+    debug: bool,
+    show_diagnostics: bool,
+    /// Counts from the last process scan
+    diagnostics: Option<session::ScanDiagnostics>,
+    /// Damaged sessions-index.json files seen this run
+    index_warnings: Vec<String>,
+    /// Frames drawn so far; advances the busy-session spinner
+    frame_count: u64,
+    /// One row per session, without the message preview
+    compact_cards: bool,
+    /// Log search query (highlighted in the log pane; n/N jump between matches)
+    log_search: String,
+    /// Typing the log search query
+    log_search_input: bool,
+    /// Index of the match last jumped to, newest first
+    log_match: usize,
+    /// Set when the last refresh couldn't look for sessions at all
+    lookup_error: Option<session::LookupError>,
+    focus: Focus,
+    /// Cards that fit in the list at the last draw (one page for PageUp/PageDown)
+    visible_cards: Cell<usize>,
 }
 
 impl App {
-    fn new() -> Self {
-        Self {
+    /// Create the app with the view and selection saved on last exit
+    fn new(config: Config) -> Self {
+        let saved = state::load();
+        let show_sidechains = config.show_sidechains;
+        let group_by_repo = config.group_by_repo;
+        let mut app = Self {
+            theme: if color::no_color_requested() {
+                Theme::monochrome()
+            } else {
+                Theme::by_name(&config.theme).unwrap_or_default()
+            },
+            keymap: Keymap::new(&config.keys).0,
+            log_cache: LogCache::new(config.log_cache_size),
+            log_scroll: LogScroll::default(),
+            log_offsets: HashMap::new(),
+            log_wrap: true,
+            log_role_filter: RoleFilter::default(),
+            log_show_tools: true,
+            pending_g: false,
+            pending_digits: String::new(),
+            config,
+            fetched_sessions: Vec::new(),
             sessions: Vec::new(),
+            total_sessions: 0,
             selected: 0,
             should_quit: false,
             log_messages: Vec::new(),
             last_log_mtime: None,
-            view_mode: ViewMode::Running,
+            view_mode: saved.view_mode,
+            show_last_message: false,
+            show_created_time: false,
+            truecolor: color::supports_truecolor(),
+            busy_only: false,
+            waiting_only: false,
+            hide_idle: false,
+            group_by_repo,
+            repo_dirs: HashMap::new(),
+            log_follow_latest: false,
+            show_attention: true,
+            filter_query: String::new(),
+            filter_input: false,
+            command_input: None,
+            overlay: None,
+            known_tmux_locations: HashMap::new(),
+            pending_kills: Vec::new(),
+            clipboard: None,
+            flash: None,
+            mux: mux::detect(),
+            exit_command: None,
+            here_command: None,
+            edit_path: None,
+            paused: false,
+            read_only: false,
+            sort_mode: SortMode::default(),
+            busy_since: HashMap::new(),
+            cpu_history: HashMap::new(),
+            show_sidechains,
+            pinned: saved.pinned_session_ids.into_iter().collect(),
+            debug: false,
+            show_diagnostics: false,
+            diagnostics: None,
+            index_warnings: Vec::new(),
+            frame_count: 0,
+            lookup_error: None,
+            compact_cards: false,
+            log_search: String::new(),
+            log_search_input: false,
+            log_match: 0,
+            focus: Focus::default(),
+            visible_cards: Cell::new(1),
+        };
+        app.refresh_sessions();
+        if let Some(id) = saved.selected_session_id {
+            if let Some(idx) = app.sessions.iter().position(|s| s.id == id) {
+                app.select(idx);
+            }
         }
+        app
+    }
+
+    /// Remember the view and selection for the next launch
+    fn save_state(&self) {
+        state::save(&state::State {
+            view_mode: self.view_mode,
+            selected_session_id: self.sessions.get(self.selected).map(|s| s.id.clone()),
+            pinned_session_ids: self.pinned.iter().cloned().collect(),
+        });
+    }
+
+    fn status_options(&self) -> session::StatusOptions {
+        session::StatusOptions::new(&self.config)
     }
 
     fn refresh_sessions(&mut self) {
-        self.sessions = match self.view_mode {
-            ViewMode::Running => session::get_sessions(),
-            ViewMode::All => session::get_all_sessions(),
+        self.check_pending_kills();
+        self.lookup_error = session::resolve_projects_dirs().err();
+        self.fetched_sessions = match self.view_mode {
+            ViewMode::Running => session::get_sessions(self.mux.as_ref(), &self.status_options()),
+            ViewMode::All => session::get_all_sessions(
+                self.mux.as_ref(),
+                &self.status_options(),
+                self.config.history_limit,
+                self.show_sidechains,
+            ),
         };
+        if self.config.remember_tmux_locations {
+            self.apply_known_tmux_locations();
+        }
+        self.diagnostics = session::last_scan_diagnostics();
+        let index_warnings = session::take_index_warnings();
+        if let Some(w) = index_warnings.last() {
+            self.flash = Some(w.clone());
+        }
+        self.index_warnings.extend(index_warnings);
+        if let Some(e) = self.mux.take_error() {
+            self.flash = Some(e);
+        }
+        self.track_busy_streaks();
+        self.record_cpu_history();
+        // Forget scroll positions of sessions no longer listed
+        self.log_offsets.retain(|id, _| self.fetched_sessions.iter().any(|s| &s.id == id));
+        // Compute "needs me" flags from the configured heuristics
+        for s in &mut self.fetched_sessions {
+            s.needs_attention = session::needs_attention(s, &self.config.attention);
+        }
+        if self.group_by_repo {
+            self.update_repo_dirs();
+        }
+        self.sort_fetched();
+        self.apply_filters();
+        // Refresh log for selected session
+        self.refresh_log();
+    }
+
+    /// Check a session against the active filters
+    fn matches_filters(&self, session: &Session) -> bool {
+        if self.busy_only && session.cpu_usage <= self.config.cpu_threshold {
+            return false;
+        }
+        if self.waiting_only && !(session.is_running && session.status == SessionStatus::Waiting) {
+            return false;
+        }
+        if self.hide_idle && session.is_running && session.status == SessionStatus::Idle {
+            return false;
+        }
+        if !self.filter_query.is_empty() {
+            let query = self.filter_query.to_lowercase();
+            let in_name = session.project_name.to_lowercase().contains(&query);
+            let in_message = session.last_message.as_deref()
+                .map(|m| m.to_lowercase().contains(&query))
+                .unwrap_or(false);
+            if !in_name && !in_message {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Derive the visible list from the last fetch (no process rescan),
+    /// keeping the same session selected if it's still listed
+    fn apply_filters(&mut self) {
+        let selected_id = self.sessions.get(self.selected).map(|s| s.id.clone());
+        self.sessions = self.fetched_sessions.iter()
+            .filter(|s| self.matches_filters(s))
+            .cloned()
+            .collect();
+        // Cap rendered sessions to keep large histories snappy
+        self.total_sessions = self.sessions.len();
+        if self.config.max_rendered_sessions > 0 {
+            self.sessions.truncate(self.config.max_rendered_sessions);
+        }
+        if let Some(idx) = selected_id.and_then(|id| self.sessions.iter().position(|s| s.id == id)) {
+            self.selected = idx;
+        }
         // Keep selection in bounds
         if self.selected >= self.sessions.len() && !self.sessions.is_empty() {
             self.selected = self.sessions.len() - 1;
         }
-        // Refresh log for selected session
-        self.refresh_log();
+    }
+
+    /// Start a timer when a session turns busy; drop it when it stops
+    fn track_busy_streaks(&mut self) {
+        let now = Instant::now();
+        let busy_ids: HashSet<&str> = self.fetched_sessions.iter()
+            .filter(|s| s.is_running && matches!(s.status, SessionStatus::Thinking | SessionStatus::Processing))
+            .map(|s| s.id.as_str())
+            .collect();
+        self.busy_since.retain(|id, _| busy_ids.contains(id.as_str()));
+        for id in busy_ids {
+            self.busy_since.entry(id.to_string()).or_insert(now);
+        }
+    }
+
+    /// Add this refresh's CPU sample for each running session; drop the
+    /// history of sessions that are gone
+    fn record_cpu_history(&mut self) {
+        let running: HashMap<&str, f32> = self.fetched_sessions.iter()
+            .filter(|s| s.is_running)
+            .map(|s| (s.id.as_str(), s.cpu_usage))
+            .collect();
+        self.cpu_history.retain(|id, _| running.contains_key(id.as_str()));
+        for (id, cpu) in running {
+            let samples = self.cpu_history.entry(id.to_string()).or_default();
+            if samples.len() == CPU_HISTORY_LEN {
+                samples.pop_front();
+            }
+            samples.push_back(cpu);
+        }
+    }
+
+    /// Fill in missing tmux locations from the last refresh that had them
+    /// (pane PIDs change across a tmux server restart), marking them stale
+    fn apply_known_tmux_locations(&mut self) {
+        for s in self.fetched_sessions.iter_mut().filter(|s| s.is_running) {
+            match s.tmux_location {
+                Some(ref loc) => {
+                    self.known_tmux_locations.insert(s.id.clone(), loc.clone());
+                }
+                None => {
+                    if let Some(loc) = self.known_tmux_locations.get(&s.id) {
+                        s.tmux_target = Some(loc.to_string());
+                        s.tmux_location = Some(loc.clone());
+                        s.tmux_stale = true;
+                    }
+                }
+            }
+        }
+
+        // Forget sessions that are no longer running
+        let running: HashSet<&str> = self.fetched_sessions.iter()
+            .filter(|s| s.is_running)
+            .map(|s| s.id.as_str())
+            .collect();
+        self.known_tmux_locations.retain(|id, _| running.contains(id.as_str()));
     }
 
     fn refresh_log(&mut self) {
         self.refresh_log_if_changed(false);
     }
 
+    /// Re-parse the current log from scratch, for when mtimes can't be
+    /// trusted (e.g. network filesystems); sessions aren't re-detected
+    fn reload_log(&mut self) {
+        if let Some(path) = self.sessions.get(self.selected)
+            .and_then(|s| log_view::resolve_log_path(s, self.log_follow_latest))
+        {
+            self.log_cache.forget(&path);
+        }
+        self.refresh_log();
+        self.flash = Some("log reloaded".to_string());
+    }
+
     fn refresh_log_if_changed(&mut self, check_mtime: bool) {
         if let Some(session) = self.sessions.get(self.selected) {
-            // Check if file changed (skip expensive parse if unchanged)
-            if check_mtime {
-                let current_mtime = log_view::get_log_mtime(&session.project_path);
-                if current_mtime == self.last_log_mtime {
-                    return; // No change, skip parsing
+            let jsonl_path = match log_view::resolve_log_path(session, self.log_follow_latest) {
+                Some(p) => p,
+                None => {
+                    self.log_messages.clear();
+                    self.last_log_mtime = None;
+                    return;
                 }
-                self.last_log_mtime = current_mtime;
-            } else {
-                self.last_log_mtime = log_view::get_log_mtime(&session.project_path);
+            };
+
+            // Check if file changed (skip expensive parse if unchanged)
+            let current_mtime = log_view::get_log_mtime(&jsonl_path);
+            if check_mtime && current_mtime == self.last_log_mtime {
+                return; // No change, skip parsing
             }
-            self.log_messages = log_view::parse_log_messages(&session.project_path);
+            self.last_log_mtime = current_mtime;
+
+            // Reuse the cached parse, reading only what was appended since
+            self.log_messages = self.log_cache.load(&jsonl_path, current_mtime);
         } else {
             self.log_messages.clear();
             self.last_log_mtime = None;
         }
     }
 
+    /// Select a session and show its log where it was last left (from the
+    /// newest message the first time)
+    fn select(&mut self, idx: usize) {
+        if idx < self.sessions.len() {
+            if let Some(id) = self.sessions.get(self.selected).map(|s| s.id.clone()) {
+                let offset = self.log_scroll.offset.min(self.log_scroll.max_offset.get());
+                if offset > 0 {
+                    self.log_offsets.insert(id, offset);
+                } else {
+                    self.log_offsets.remove(&id);
+                }
+            }
+            self.selected = idx;
+            self.log_scroll.offset = self.log_offsets.get(&self.sessions[idx].id).copied().unwrap_or(0);
+            self.refresh_log();
+        }
+    }
+
     fn select_next(&mut self) {
         if !self.sessions.is_empty() {
-            self.selected = (self.selected + 1) % self.sessions.len();
-            self.refresh_log();
+            self.select((self.selected + 1) % self.sessions.len());
         }
     }
 
     fn select_prev(&mut self) {
         if !self.sessions.is_empty() {
-            self.selected = self.selected.checked_sub(1).unwrap_or(self.sessions.len() - 1);
-            self.refresh_log();
+            self.select(self.selected.checked_sub(1).unwrap_or(self.sessions.len() - 1));
+        }
+    }
+
+    /// Move the selection a page down (or up), stopping at either end
+    fn select_page(&mut self, down: bool) {
+        if self.sessions.is_empty() {
+            return;
         }
+        let page = self.visible_cards.get().max(1);
+        let idx = if down {
+            (self.selected + page).min(self.sessions.len() - 1)
+        } else {
+            self.selected.saturating_sub(page)
+        };
+        self.select(idx);
     }
 
-    /// Go to or resume selected session
-    fn go_to_selected(&self) -> bool {
+    fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Sessions => Focus::Log,
+            Focus::Log => Focus::Sessions,
+        };
+    }
+
+    /// Go to or resume selected session; with `here`, a resume runs in the
+    /// current pane once we've exited instead of in a new window
+    fn go_to_selected(&mut self, here: bool) -> bool {
+        if self.deny_read_only() {
+            return false;
+        }
         if let Some(session) = self.sessions.get(self.selected) {
-            // Running session with tmux: switch to it
+            // Running session with a known pane: switch to it
             if session.is_running {
                 if let Some(ref loc) = session.tmux_location {
-                    tmux::switch_to_window(loc);
-                    return true;
+                    // On failure, don't resume a second copy of a live session;
+                    // print a way to reach it by hand if the backend has one
+                    return match self.mux.switch_to(loc) {
+                        Ok(()) => true,
+                        Err(e) => match self.mux.attach_command(loc) {
+                            Some(cmd) => {
+                                self.exit_command = Some(cmd);
+                                true
+                            }
+                            None => {
+                                self.flash = Some(format!("{} can't switch to {}: {}", self.mux.name(), loc, e));
+                                false
+                            }
+                        },
+                    };
                 }
             }
-            // Otherwise: resume in new tmux window
-            tmux::new_window_with_command(&session.project_name, &session.project_path, &session.id);
+            // Resuming in a deleted directory opens a window that dies at once
+            if !Path::new(&session.project_path).is_dir() {
+                self.flash = Some("project directory no longer exists".to_string());
+                return false;
+            }
+            // Otherwise: resume here or in a new window, or print the command on exit
+            let cmd = mux::resume_command(&self.config, &session.project_path, &session.id);
+            if here {
+                self.here_command = Some(cmd);
+                return true;
+            }
+            let name = session.project_name.clone();
+            return self.open_window(&name, cmd);
+        }
+        false
+    }
+
+    /// Run `cmd` in a new window. If there's nowhere to open one, print it on
+    /// exit instead; if opening failed, flash why and keep running.
+    fn open_window(&mut self, name: &str, cmd: String) -> bool {
+        // Drop a background error so it isn't reported as this failure
+        let _ = self.mux.take_error();
+        if self.mux.new_window_with_command(name, &cmd) {
             return true;
         }
+        if let Some(e) = self.mux.take_error() {
+            self.flash = Some(e);
+            return false;
+        }
+        self.exit_command = Some(cmd);
+        true
+    }
+
+    /// In read-only mode, flash a notice and report that the action is blocked
+    fn deny_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.flash = Some("read-only".to_string());
+        }
+        self.read_only
+    }
+
+    /// Start a fresh Claude session in the selected session's project
+    fn new_session_selected(&mut self) -> bool {
+        if self.deny_read_only() {
+            return false;
+        }
+        if let Some(session) = self.sessions.get(self.selected) {
+            if !Path::new(&session.project_path).is_dir() {
+                self.flash = Some("project directory no longer exists".to_string());
+                return false;
+            }
+            let cmd = mux::new_session_command(&self.config, &session.project_path);
+            let name = session.project_name.clone();
+            return self.open_window(&name, cmd);
+        }
         false
     }
 
-    fn kill_selected(&mut self) {
+    /// Send SIGTERM to the selected session, escalating to SIGKILL if it
+    /// outlives KILL_ESCALATE_AFTER. With `force` the wait is
+    /// FORCE_KILL_ESCALATE_AFTER, and SIGKILL goes out at once if SIGTERM can't.
+    fn kill_selected(&mut self, force: bool) {
+        if self.deny_read_only() {
+            return;
+        }
         if let Some(session) = self.sessions.get(self.selected) {
             if let Some(pid) = session.pid {
-                unsafe { libc::kill(pid as i32, libc::SIGTERM); }
-                tmux::notify(&format!("Killed: {}", session.project_name));
+                let termed = process::terminate(pid, false);
+                let killed = !termed && force && process::terminate(pid, true);
+                let sent = termed || killed;
+                let msg = match (sent, force) {
+                    (true, false) => format!("Killing: {}", session.project_name),
+                    (true, true) => format!("Force killing: {}", session.project_name),
+                    (false, _) => format!("Failed to kill: {}", session.project_name),
+                };
+                if sent {
+                    self.pending_kills.push(PendingKill {
+                        pid,
+                        session_id: session.id.clone(),
+                        sent_at: Instant::now(),
+                        escalate_after: if force { FORCE_KILL_ESCALATE_AFTER } else { KILL_ESCALATE_AFTER },
+                        forced: killed,
+                        tmux_location: session.tmux_location.clone(),
+                    });
+                }
+                self.mux.notify(&msg);
                 self.refresh_sessions();
             }
         }
     }
 
+    /// Extend the numeric jump buffer and select the session it names.
+    /// Out-of-range numbers are ignored; the buffer stays open while a
+    /// further digit could still name a session.
+    fn push_jump_digit(&mut self, mut digits: String, c: char) {
+        digits.push(c);
+        let n: usize = match digits.parse() {
+            Ok(n) => n,
+            Err(_) => return,
+        };
+        if (1..=self.sessions.len()).contains(&n) {
+            self.select(n - 1);
+        }
+        if n > 0 && n * 10 <= self.sessions.len() {
+            self.pending_digits = digits;
+        }
+    }
+
+    /// Copy the selected session's last message to the clipboard
+    fn copy_last_message(&mut self) {
+        let text = self.sessions.get(self.selected).and_then(|s| s.last_message.clone());
+        self.copy_to_clipboard(text);
+    }
+
+    /// Copy the log pane's most recent assistant message (untruncated)
+    fn copy_log_message(&mut self) {
+        let text = self.log_messages.iter()
+            .rev()
+            .find(|m| m.role == "assistant" && m.kind == LogKind::Text && !m.content.trim().is_empty())
+            .map(|m| m.content.clone());
+        self.copy_to_clipboard(text);
+    }
+
+    /// Write the session's whole conversation to `<project>-<id>.md` in the
+    /// current directory, flashing where it went
+    fn export_session(&mut self, session_id: &str) {
+        let Some(session) = self.sessions.iter().find(|s| s.id == session_id) else { return };
+        let Some(jsonl_path) = session.jsonl_path.clone() else {
+            self.flash = Some("no log to export".to_string());
+            return;
+        };
+        let project: String = session.project_name.chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
+            .collect();
+        let out = std::env::current_dir()
+            .unwrap_or_default()
+            .join(format!("{}-{}.md", project, session.id));
+        let title = format!("{} — {}", session.project_name, session.id);
+
+        self.flash = Some(match log_view::export_markdown(Path::new(&jsonl_path), &out, &title) {
+            Ok(_) => format!("exported to {}", out.display()),
+            Err(e) => format!("export failed: {}", e),
+        });
+    }
+
+    /// Ask the main loop to open the selected session's JSONL in $EDITOR
+    fn open_selected_log(&mut self) {
+        let Some(session) = self.sessions.get(self.selected) else { return };
+        match log_view::resolve_log_path(session, false) {
+            Some(path) => self.edit_path = Some(path),
+            None => self.flash = Some("no log to open".to_string()),
+        }
+    }
+
+    fn export_selected(&mut self) {
+        if let Some(id) = self.sessions.get(self.selected).map(|s| s.id.clone()) {
+            self.export_session(&id);
+        }
+    }
+
+    fn copy_to_clipboard(&mut self, text: Option<String>) {
+        let Some(text) = text else { return };
+
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        let copied = self.clipboard.as_mut()
+            .map(|c| c.set_text(text).is_ok())
+            .unwrap_or(false);
+
+        self.flash = Some(if copied { "copied" } else { "clipboard unavailable" }.to_string());
+    }
+
+    /// Drop exited processes from pending kills (closing their window if
+    /// configured); SIGKILL any that outlived the timeout
+    fn check_pending_kills(&mut self) {
+        let kill_window = self.config.kill_tmux_window;
+        let mux = self.mux.as_ref();
+        self.pending_kills.retain_mut(|kill| {
+            if !process::is_alive(kill.pid) {
+                // Its pane may have closed with it
+                tmux::invalidate_pane_map();
+                if let (true, Some(loc)) = (kill_window, &kill.tmux_location) {
+                    mux.kill_window(loc);
+                }
+                return false;
+            }
+            if !kill.forced && kill.sent_at.elapsed() >= kill.escalate_after {
+                process::terminate(kill.pid, true);
+                kill.forced = true;
+            }
+            true
+        });
+    }
+
+    fn is_being_killed(&self, session_id: &str) -> bool {
+        self.pending_kills.iter().any(|k| k.session_id == session_id)
+    }
+
     fn toggle_view_mode(&mut self) {
         self.view_mode = self.view_mode.toggle();
         self.refresh_sessions();
     }
 
+    fn set_view_mode(&mut self, mode: ViewMode) {
+        if self.view_mode != mode {
+            self.toggle_view_mode();
+        }
+    }
+
+    fn start_command(&mut self) {
+        self.command_input = Some(String::new());
+    }
+
+    fn handle_command_key(&mut self, code: KeyCode) {
+        let Some(ref mut input) = self.command_input else { return };
+        match code {
+            KeyCode::Enter => {
+                let input = std::mem::take(input);
+                self.command_input = None;
+                self.run_command(&input);
+            }
+            KeyCode::Esc => self.command_input = None,
+            // Backspace on an empty prompt closes it, like vim
+            KeyCode::Backspace if input.is_empty() => self.command_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Parse and run a `:` command, flashing any parse error
+    fn run_command(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+        let command = match PromptCommand::parse(input) {
+            Ok(c) => c,
+            Err(e) => {
+                self.flash = Some(e);
+                return;
+            }
+        };
+        match command {
+            PromptCommand::Kill { force } => self.request_kill(force),
+            PromptCommand::Delete => self.request_delete(),
+            PromptCommand::Resume { here } => self.should_quit = self.go_to_selected(here),
+            PromptCommand::New => self.should_quit = self.new_session_selected(),
+            PromptCommand::Sort(mode) => self.set_sort_mode(mode),
+            PromptCommand::Filter(query) => self.set_filter_query(query),
+            PromptCommand::View(mode) => self.set_view_mode(mode),
+            PromptCommand::Refresh => self.refresh_sessions(),
+            PromptCommand::Pause => self.toggle_paused(),
+            PromptCommand::Quit => self.should_quit = true,
+        }
+    }
+
+    fn toggle_busy_only(&mut self) {
+        self.busy_only = !self.busy_only;
+        self.apply_filters();
+        self.refresh_log();
+    }
+
+    fn toggle_waiting_only(&mut self) {
+        self.waiting_only = !self.waiting_only;
+        self.apply_filters();
+        self.refresh_log();
+    }
+
+    fn toggle_hide_idle(&mut self) {
+        self.hide_idle = !self.hide_idle;
+        self.apply_filters();
+        self.refresh_log();
+    }
+
+    fn start_filter(&mut self) {
+        self.filter_input = true;
+    }
+
+    /// Keys while typing a filter query
+    fn handle_filter_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Enter => self.filter_input = false,
+            KeyCode::Esc => {
+                self.filter_input = false;
+                self.set_filter_query(String::new());
+            }
+            KeyCode::Backspace => {
+                let mut query = self.filter_query.clone();
+                query.pop();
+                self.set_filter_query(query);
+            }
+            KeyCode::Char(c) => {
+                let query = format!("{}{}", self.filter_query, c);
+                self.set_filter_query(query);
+            }
+            _ => {}
+        }
+    }
+
+    fn start_log_search(&mut self) {
+        self.log_search_input = true;
+        self.log_search.clear();
+    }
+
+    fn handle_log_search_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Enter => {
+                self.log_search_input = false;
+                self.jump_to_log_match(0);
+            }
+            KeyCode::Esc => self.clear_log_search(),
+            KeyCode::Backspace => {
+                self.log_search.pop();
+            }
+            KeyCode::Char(c) => self.log_search.push(c),
+            _ => {}
+        }
+    }
+
+    fn clear_log_search(&mut self) {
+        self.log_search_input = false;
+        self.log_search.clear();
+    }
+
+    /// Move to the next (older) or previous (newer) log search match, wrapping
+    fn step_log_match(&mut self, forward: bool) {
+        let count = self.log_scroll.match_offsets.borrow().len();
+        if count == 0 {
+            self.flash = Some("no matches".to_string());
+            return;
+        }
+        let index = if forward {
+            (self.log_match + 1) % count
+        } else {
+            (self.log_match + count - 1) % count
+        };
+        self.jump_to_log_match(index);
+    }
+
+    fn jump_to_log_match(&mut self, index: usize) {
+        self.log_match = index;
+        let count = self.log_scroll.jump_to_match(index);
+        self.flash = Some(match count {
+            0 => "no matches".to_string(),
+            n => format!("match {} of {}", index + 1, n),
+        });
+    }
+
+    fn set_filter_query(&mut self, query: String) {
+        self.filter_query = query;
+        self.apply_filters();
+        self.refresh_log();
+    }
+
+    /// Title label: view mode plus any active filters
+    fn view_label(&self) -> String {
+        let mut label = format!("{} · {}", self.view_mode.label(), self.sort_mode.label());
+        if self.busy_only {
+            label.push_str(" · busy");
+        }
+        if self.waiting_only {
+            label.push_str(" · waiting");
+        }
+        if self.hide_idle {
+            label.push_str(" · no idle");
+        }
+        if self.show_created_time {
+            label.push_str(" · created");
+        }
+        label
+    }
+
+    fn cycle_sort_mode(&mut self) {
+        self.set_sort_mode(self.sort_mode.next());
+    }
+
+    /// Switch the sort order, re-sorting the current fetch in place
+    fn set_sort_mode(&mut self, mode: SortMode) {
+        self.sort_mode = mode;
+        self.sort_fetched();
+        self.apply_filters();
+    }
+
+    /// Order the current fetch: sort mode, then flagged sessions first
+    /// (when configured), then pinned sessions above everything
+    fn sort_fetched(&mut self) {
+        self.sort_mode.sort(&mut self.fetched_sessions);
+        if self.config.attention.float_to_top {
+            self.fetched_sessions.sort_by_key(|s| !s.needs_attention);
+        }
+        self.fetched_sessions.sort_by_key(|s| !self.pinned.contains(&s.id));
+        if self.group_by_repo {
+            self.sort_by_repo();
+        }
+    }
+
+    /// Cluster sessions of the same repo (any worktree), groups in the order
+    /// of their first session and sessions outside a repo last
+    fn sort_by_repo(&mut self) {
+        let mut order: Vec<&str> = Vec::new();
+        for s in &self.fetched_sessions {
+            if let Some(dir) = self.repo_dir(s) {
+                if !order.contains(&dir) {
+                    order.push(dir);
+                }
+            }
+        }
+        let rank: HashMap<String, usize> = order.iter().enumerate().map(|(i, d)| (d.to_string(), i)).collect();
+        let mut sessions = std::mem::take(&mut self.fetched_sessions);
+        sessions.sort_by_key(|s| self.repo_dir(s).map_or(usize::MAX, |d| rank[d]));
+        self.fetched_sessions = sessions;
+    }
+
+    /// Look up the repo of every fetched session (cached with a TTL), so
+    /// sorting and drawing only read `repo_dirs`
+    fn update_repo_dirs(&mut self) {
+        self.repo_dirs = self.fetched_sessions.iter()
+            .map(|s| (s.project_path.clone(), session::repo_dir(&s.project_path)))
+            .collect();
+    }
+
+    /// Shared git directory of a session's repo, once `update_repo_dirs` has looked
+    fn repo_dir(&self, session: &Session) -> Option<&str> {
+        self.repo_dirs.get(&session.project_path)?.as_deref()
+    }
+
+    fn toggle_group_by_repo(&mut self) {
+        self.group_by_repo = !self.group_by_repo;
+        if self.group_by_repo {
+            self.update_repo_dirs();
+        }
+        self.sort_fetched();
+        self.apply_filters();
+    }
+
+    fn is_pinned(&self, session_id: &str) -> bool {
+        self.pinned.contains(session_id)
+    }
+
+    /// Pin or unpin the selected session, saving right away
+    fn toggle_pin(&mut self) {
+        let id = match self.sessions.get(self.selected) {
+            Some(s) => s.id.clone(),
+            None => return,
+        };
+        let pinned = if self.pinned.remove(&id) {
+            false
+        } else {
+            self.pinned.insert(id);
+            true
+        };
+        self.flash = Some(if pinned { "pinned" } else { "unpinned" }.to_string());
+        self.sort_fetched();
+        self.apply_filters();
+        self.save_state();
+    }
+
+    /// Jump to the next session that needs attention (wrapping)
+    fn select_next_attention(&mut self) {
+        let len = self.sessions.len();
+        let next = (1..=len)
+            .map(|offset| (self.selected + offset) % len)
+            .find(|&i| self.sessions[i].needs_attention);
+        if let Some(idx) = next {
+            self.select(idx);
+        }
+    }
+
+    fn toggle_compact_cards(&mut self) {
+        self.compact_cards = !self.compact_cards;
+    }
+
+    fn toggle_diagnostics(&mut self) {
+        if self.debug {
+            self.show_diagnostics = !self.show_diagnostics;
+        }
+    }
+
+    fn toggle_sidechains(&mut self) {
+        self.show_sidechains = !self.show_sidechains;
+        self.refresh_sessions();
+    }
+
+    fn toggle_paused(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    fn toggle_attention_badge(&mut self) {
+        self.show_attention = !self.show_attention;
+    }
+
+    /// Open the process tree overlay for the selected running session
+    fn show_process_tree(&mut self) {
+        if let Some(session) = self.sessions.get(self.selected) {
+            if let Some(pid) = session.pid {
+                self.overlay = Some(Overlay::ProcessTree {
+                    ancestors: process::get_ancestry(pid),
+                    tmux_target: session.tmux_target.clone(),
+                });
+            }
+        }
+    }
+
+    /// Open the metadata overlay for the selected session
+    fn show_detail(&mut self) {
+        if let Some(session) = self.sessions.get(self.selected) {
+            self.overlay = Some(Overlay::Detail { session_id: session.id.clone() });
+        }
+    }
+
+    /// Ask before killing the selected running session
+    fn request_kill(&mut self, force: bool) {
+        if self.deny_read_only() {
+            return;
+        }
+        if let Some(session) = self.sessions.get(self.selected) {
+            if session.pid.is_some() {
+                let (action, verb) = if force {
+                    (ConfirmAction::ForceKill, "Force kill")
+                } else {
+                    (ConfirmAction::Kill, "Kill")
+                };
+                self.overlay = Some(Overlay::Confirm {
+                    action,
+                    session_id: session.id.clone(),
+                    message: format!("{} session {}?", verb, session.project_name),
+                });
+            }
+        }
+    }
+
+    /// Ask before deleting the selected historical session
+    fn request_delete(&mut self) {
+        if self.deny_read_only() {
+            return;
+        }
+        if let Some(session) = self.sessions.get(self.selected) {
+            if !session.is_running {
+                self.overlay = Some(Overlay::Confirm {
+                    action: ConfirmAction::Delete,
+                    session_id: session.id.clone(),
+                    message: format!("Delete session {}?", session.project_name),
+                });
+            }
+        }
+    }
+
+    /// Run a confirmed action against its session, even if the list re-sorted meanwhile
+    fn confirm(&mut self, action: ConfirmAction, session_id: &str) {
+        if let Some(idx) = self.sessions.iter().position(|s| s.id == session_id) {
+            self.selected = idx;
+            match action {
+                ConfirmAction::Kill => self.kill_selected(false),
+                ConfirmAction::ForceKill => self.kill_selected(true),
+                ConfirmAction::Delete => self.delete_selected(),
+            }
+        }
+    }
+
+    /// Run a main-view action; `pending_g` is set when the previous key
+    /// was a first `g`
+    fn perform(&mut self, action: Action, pending_g: bool) {
+        let log_focused = self.focus == Focus::Log;
+        match action {
+            // j/k, paging, and `/` act on the focused pane
+            Action::Down if log_focused => self.log_scroll.down(1),
+            Action::Up if log_focused => self.log_scroll.up(1),
+            Action::Down => self.select_next(),
+            Action::Up => self.select_prev(),
+            // Log scrolling (offset 0 = newest message)
+            Action::PageDown if log_focused => self.log_scroll.down(self.log_scroll.page()),
+            Action::PageUp if log_focused => self.log_scroll.up(self.log_scroll.page()),
+            Action::PageDown => self.select_page(true),
+            Action::PageUp => self.select_page(false),
+            Action::HalfPageDown => self.log_scroll.down(self.log_scroll.half_page()),
+            Action::HalfPageUp => self.log_scroll.up(self.log_scroll.half_page()),
+            Action::LogNewest if pending_g => self.log_scroll.jump_newest(),
+            Action::LogNewest => self.pending_g = true,
+            Action::LogOldest => self.log_scroll.jump_oldest(),
+            Action::ScrollLeft => self.log_scroll.left(LOG_H_SCROLL_STEP),
+            Action::ScrollRight => self.log_scroll.right(LOG_H_SCROLL_STEP),
+            Action::ToggleWrap => self.toggle_log_wrap(),
+            Action::CycleRoleFilter => self.cycle_log_role_filter(),
+            Action::ToggleTools => self.toggle_log_tools(),
+            Action::ToggleLogSource => self.toggle_log_source(),
+            Action::ReloadLog => self.reload_log(),
+            Action::SearchLog => self.start_log_search(),
+            Action::Filter if log_focused => self.start_log_search(),
+            Action::Filter => self.start_filter(),
+            Action::Command => self.start_command(),
+            Action::Help => self.overlay = Some(Overlay::Help),
+            // Esc clears an active log search, leaves the log pane, or
+            // clears the filter before quitting
+            Action::Back if !self.log_search.is_empty() => self.clear_log_search(),
+            Action::Back if log_focused => self.focus = Focus::Sessions,
+            Action::Back if !self.filter_query.is_empty() => self.set_filter_query(String::new()),
+            Action::Back | Action::Quit => self.should_quit = true,
+            Action::Go => self.should_quit = self.go_to_selected(false),
+            Action::ResumeHere => self.should_quit = self.go_to_selected(true),
+            Action::NewSession => self.should_quit = self.new_session_selected(),
+            Action::Kill => self.request_kill(false),
+            Action::ForceKill => self.request_kill(true),
+            Action::Delete => self.request_delete(),
+            Action::Pin => self.toggle_pin(),
+            Action::CopyMessage => self.copy_last_message(),
+            Action::CopyLogMessage => self.copy_log_message(),
+            Action::Export => self.export_selected(),
+            Action::OpenInEditor => self.open_selected_log(),
+            Action::Refresh => self.refresh_sessions(),
+            Action::ToggleView => self.toggle_view_mode(),
+            Action::ToggleFocus => self.toggle_focus(),
+            Action::CycleSort => self.cycle_sort_mode(),
+            Action::BusyOnly => self.toggle_busy_only(),
+            Action::WaitingOnly => self.toggle_waiting_only(),
+            Action::HideIdle => self.toggle_hide_idle(),
+            Action::GroupByRepo => self.toggle_group_by_repo(),
+            Action::ToggleSidechains => self.toggle_sidechains(),
+            Action::ToggleLastMessage => self.toggle_history_message(),
+            Action::ToggleCreatedTime => self.show_created_time = !self.show_created_time,
+            Action::ToggleCompact => self.toggle_compact_cards(),
+            Action::ToggleAttentionBadge => self.toggle_attention_badge(),
+            Action::NextAttention => self.select_next_attention(),
+            Action::Pause => self.toggle_paused(),
+            Action::Detail => self.show_detail(),
+            Action::ProcessTree => self.show_process_tree(),
+            Action::Diagnostics => self.toggle_diagnostics(),
+        }
+    }
+
+    /// Keys while an overlay is open; everything else is swallowed
+    fn handle_overlay_key(&mut self, code: KeyCode) {
+        match self.overlay.take() {
+            Some(Overlay::Confirm { action, session_id, message }) => match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.confirm(action, &session_id),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {}
+                _ => self.overlay = Some(Overlay::Confirm { action, session_id, message }),
+            },
+            Some(Overlay::ProcessTree { .. }) if matches!(code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t')) => {}
+            Some(Overlay::Detail { .. }) if matches!(code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('i')) => {}
+            Some(Overlay::Detail { session_id }) if code == KeyCode::Char('e') => {
+                self.export_session(&session_id);
+                self.overlay = Some(Overlay::Detail { session_id });
+            }
+            Some(Overlay::Help) if matches!(code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?')) => {}
+            other => self.overlay = other,
+        }
+    }
+
+    fn toggle_log_wrap(&mut self) {
+        self.log_wrap = !self.log_wrap;
+        self.log_scroll.h_offset = 0;
+    }
+
+    fn toggle_log_tools(&mut self) {
+        self.log_show_tools = !self.log_show_tools;
+    }
+
+    fn cycle_log_role_filter(&mut self) {
+        self.log_role_filter = self.log_role_filter.next();
+        self.log_scroll.jump_newest();
+    }
+
+    fn toggle_log_source(&mut self) {
+        self.log_follow_latest = !self.log_follow_latest;
+        self.refresh_log();
+    }
+
+    fn toggle_history_message(&mut self) {
+        self.show_last_message = !self.show_last_message;
+    }
+
     /// Delete a historical session
     fn delete_selected(&mut self) {
+        if self.deny_read_only() {
+            return;
+        }
         if let Some(session) = self.sessions.get(self.selected) {
             if !session.is_running {
                 let name = session.project_name.clone();
                 session::delete_session(session);
-                tmux::notify(&format!("Deleted: {}", name));
+                self.mux.notify(&format!("Deleted: {}", name));
                 self.refresh_sessions();
             }
         }
     }
 }
 
+/// Get the value following a `--flag value` argument
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+/// Parse the number following `flag`, exiting with an error if it isn't one
+fn numeric_arg(args: &[String], flag: &str) -> Option<u64> {
+    let value = arg_value(args, flag)?;
+    match value.parse() {
+        Ok(n) => Some(n),
+        Err(_) => {
+            eprintln!("claude-watch: {} expects a number, got '{}'", flag, value);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Leave the TUI, run `$EDITOR <path>` (or `less`) until it exits, and
+/// take the terminal back
+fn open_in_editor(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, path: &Path) -> io::Result<()> {
+    let editor = std::env::var("EDITOR").ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    // $EDITOR may carry flags, e.g. "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("less");
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status();
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+
+    status.map(|_| ()).map_err(|e| io::Error::new(e.kind(), format!("can't run {}: {}", program, e)))
+}
+
+/// Parse a `WIDTHxHEIGHT` frame size, e.g. `120x40`
+fn parse_size(value: &str) -> Option<(u16, u16)> {
+    let (w, h) = value.split_once('x')?;
+    let (w, h) = (w.parse().ok()?, h.parse().ok()?);
+    (w > 0 && h > 0).then_some((w, h))
+}
+
+/// Draw a single frame into an off-screen buffer and return it as text,
+/// one line per row with trailing spaces trimmed
+fn render_once(app: &App, width: u16, height: u16) -> io::Result<String> {
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(width, height))?;
+    terminal.draw(|f| ui::draw(f, app))?;
+
+    let buffer = terminal.backend().buffer();
+    let mut out = String::new();
+    for y in 0..height {
+        let mut line = String::new();
+        let mut x = 0;
+        while x < width {
+            let symbol = buffer[(x, y)].symbol();
+            line.push_str(symbol);
+            // A wide character covers the cells after it
+            x += Span::raw(symbol).width().max(1) as u16;
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// --once: render one frame of the TUI (no event loop) to stdout or --output
+fn run_once(config: Config, args: &[String]) -> io::Result<()> {
+    let (width, height) = match arg_value(args, "--size") {
+        None => crossterm::terminal::size().unwrap_or((120, 40)),
+        Some(v) => match parse_size(v) {
+            Some(size) => size,
+            None => {
+                eprintln!("claude-watch: --size expects WIDTHxHEIGHT (e.g. 120x40), got '{}'", v);
+                std::process::exit(2);
+            }
+        },
+    };
+
+    let mut app = App::new(config);
+    app.read_only = true;
+    if args.iter().any(|a| a == "--all") {
+        app.set_view_mode(ViewMode::All);
+    }
+    let frame = render_once(&app, width, height)?;
+    match arg_value(args, "--output") {
+        Some(path) => std::fs::write(path, frame),
+        None => {
+            print!("{}", frame);
+            Ok(())
+        }
+    }
+}
+
+/// Report damaged sessions-index.json files on stderr (CLI modes have no flash)
+fn print_index_warnings() {
+    for warning in session::take_index_warnings() {
+        eprintln!("claude-watch: {}", warning);
+    }
+}
+
+/// Print the session list as one JSON line per interval until stdout closes
+fn run_watch_json(config: &Config, include_history: bool, interval: Duration) {
+    use std::io::Write;
+
+    let mux = mux::detect();
+    let options = session::StatusOptions::new(config);
+    loop {
+        let sessions = if include_history {
+            session::get_all_sessions(mux.as_ref(), &options, config.history_limit, config.show_sidechains)
+        } else {
+            session::get_sessions(mux.as_ref(), &options)
+        };
+        print_index_warnings();
+        let line = serde_json::to_string(&sessions).unwrap_or_default();
+
+        // A closed pipe (e.g. `| head`) ends the stream quietly
+        let mut stdout = io::stdout().lock();
+        if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+            return;
+        }
+        drop(stdout);
+
+        std::thread::sleep(interval);
+    }
+}
+
+/// --version: our version, plus the installed Claude Code's when `claude_bin
+/// --version` answers (JSONL details change between Claude versions)
+fn print_version(config: &Config) {
+    println!("claude-watch {}", env!("CARGO_PKG_VERSION"));
+    let claude = std::process::Command::new(&config.claude_bin)
+        .arg("--version")
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8_lossy(&o.stdout).lines().next().map(|l| l.trim().to_string()))
+        .filter(|v| !v.is_empty());
+    match claude {
+        Some(version) => println!("{} {}", config.claude_bin, version),
+        None => println!("{}: version unknown (not installed or not on PATH)", config.claude_bin),
+    }
+}
+
+/// Report (or with `force`, delete) historical sessions older than `days`
+fn run_prune(config: &Config, days: u64, force: bool) {
+    let options = session::StatusOptions::new(config);
+    let candidates = session::get_prune_candidates(mux::detect().as_ref(), &options, days * 86400);
+    print_index_warnings();
+    if candidates.is_empty() {
+        println!("No sessions older than {} days", days);
+        return;
+    }
+
+    let total_bytes: u64 = candidates.iter()
+        .filter_map(|s| s.jsonl_path.as_ref())
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum();
+
+    let verb = if force { "Deleting" } else { "Would delete" };
+    println!("{} {} sessions ({:.1} MB):", verb, candidates.len(), total_bytes as f64 / 1_048_576.0);
+    for s in &candidates {
+        println!("  {:>4}d  {:<24}  {}", s.last_activity_secs / 86400, s.project_name, s.id);
+        if force {
+            session::delete_session(s);
+        }
+    }
+
+    if !force {
+        println!("Dry run: re-run with --force to delete");
+    }
+}
+
 fn main() -> io::Result<()> {
-    // Check for --list flag
     let args: Vec<String> = std::env::args().collect();
+    // --projects-dir may be repeated (or given a colon-separated list)
+    let projects_dirs: Vec<&str> = args.windows(2)
+        .filter(|w| w[0] == "--projects-dir")
+        .map(|w| w[1].as_str())
+        .collect();
+    if !projects_dirs.is_empty() {
+        session::set_projects_dirs(&projects_dirs);
+    }
+    // The TUI shows this in place of the list; the other modes would
+    // otherwise just print nothing
+    if let Err(e) = session::resolve_projects_dirs() {
+        eprintln!("claude-watch: {}", e);
+    }
+
+    // CLI flags override the config file
+    let mut config = config::load();
+    if let Some(n) = numeric_arg(&args, "--history-limit") {
+        config.history_limit = n as usize;
+    }
+    if let Some(ms) = numeric_arg(&args, "--session-interval") {
+        config.session_interval_ms = ms;
+    }
+    if let Some(ms) = numeric_arg(&args, "--log-interval") {
+        config.log_interval_ms = ms;
+    }
+
+    // Warn now, while stderr is still visible; the TUI falls back quietly
+    if Theme::by_name(&config.theme).is_none() {
+        eprintln!("claude-watch: unknown theme '{}', using rose-pine-moon", config.theme);
+    }
+    session::set_exclude_jsonl_prefixes(&config.exclude_jsonl_prefixes);
+    tmux::set_socket(&config.tmux_socket);
+    tmux::set_pane_map_ttl(Duration::from_millis(config.pane_map_cache_ms));
+
+    if args.iter().any(|a| a == "--version" || a == "-V") {
+        print_version(&config);
+        return Ok(());
+    }
+
+    if args.iter().any(|a| a == "--prune") {
+        let days = match arg_value(&args, "--older-than").map(|v| (v, v.parse::<u64>())) {
+            // Whole days must fit in seconds; a wrapped age would select the wrong sessions
+            Some((_, Ok(d))) if d.checked_mul(86400).is_some() => d,
+            Some((v, Ok(_))) => {
+                eprintln!("claude-watch: --older-than {} is too large", v);
+                std::process::exit(2);
+            }
+            _ => {
+                eprintln!("claude-watch: --prune requires --older-than <days>");
+                std::process::exit(2);
+            }
+        };
+        // Dry run unless --force is given
+        run_prune(&config, days, args.iter().any(|a| a == "--force"));
+        return Ok(());
+    }
+
+    // Exit 0 when some running session is waiting for input, 1 otherwise
+    let status_options = session::StatusOptions::new(&config);
+    if args.iter().any(|a| a == "--check-waiting") {
+        let waiting = session::get_sessions(mux::detect().as_ref(), &status_options).iter()
+            .filter(|s| s.status == SessionStatus::Waiting)
+            .count();
+        if args.iter().any(|a| a == "--count") {
+            println!("{}", waiting);
+        }
+        std::process::exit(if waiting > 0 { 0 } else { 1 });
+    }
+
+    // Check for --list flag
     if args.iter().any(|a| a == "--list" || a == "-l") {
-        let sessions = session::get_sessions();
+        let sessions = session::get_sessions(mux::detect().as_ref(), &status_options);
         println!("{}", serde_json::to_string_pretty(&sessions).unwrap_or_default());
         return Ok(());
     }
     if args.iter().any(|a| a == "--list-all") {
-        let sessions = session::get_all_sessions();
+        let sessions = session::get_all_sessions(
+            mux::detect().as_ref(),
+            &status_options,
+            config.history_limit,
+            config.show_sidechains,
+        );
+        print_index_warnings();
         println!("{}", serde_json::to_string_pretty(&sessions).unwrap_or_default());
         return Ok(());
     }
 
+    // Newline-delimited JSON every --interval seconds (default 2)
+    if args.iter().any(|a| a == "--watch-json") {
+        let interval = match arg_value(&args, "--interval") {
+            None => Duration::from_secs(2),
+            Some(v) => match v.parse::<f64>().ok().filter(|secs| *secs > 0.0).map(Duration::try_from_secs_f64) {
+                Some(Ok(interval)) => interval,
+                _ => {
+                    eprintln!("claude-watch: --interval expects a positive number of seconds, got '{}'", v);
+                    std::process::exit(2);
+                }
+            },
+        };
+        run_watch_json(&config, args.iter().any(|a| a == "--all"), interval);
+        return Ok(());
+    }
+
+    if args.iter().any(|a| a == "--once") {
+        return run_once(config, &args);
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -175,59 +1671,100 @@ fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app and run
-    let mut app = App::new();
-    app.refresh_sessions();
+    let mut app = App::new(config);
+    app.read_only = args.iter().any(|a| a == "--read-only");
+    if args.iter().any(|a| a == "--no-color") {
+        app.theme = Theme::monochrome();
+    }
+    app.debug = args.iter().any(|a| a == "--debug");
+    app.show_diagnostics = app.debug;
 
-    // Split refresh rates: sessions heavy (2s), log light (500ms)
-    let session_tick_rate = Duration::from_secs(2);
-    let log_tick_rate = Duration::from_millis(500);
-    let mut last_session_tick = std::time::Instant::now();
-    let mut last_log_tick = std::time::Instant::now();
+    // Split refresh rates: sessions heavy (2s default), log light (500ms default)
+    let session_tick_rate = app.config.session_interval();
+    let log_tick_rate = app.config.log_interval();
+    let mut last_session_tick = Instant::now();
+    let mut last_log_tick = Instant::now();
 
     loop {
-        terminal.draw(|f| ui::draw(f, &app.sessions, app.selected, &app.log_messages, app.view_mode.label()))?;
+        terminal.draw(|f| {
+            ui::draw(f, &app);
+            if !app.truecolor {
+                color::downgrade_buffer(f.buffer_mut());
+            }
+        })?;
+        app.flash = None;
+        app.frame_count = app.frame_count.wrapping_add(1);
 
         let timeout = log_tick_rate.saturating_sub(last_log_tick.elapsed());
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+            let event = event::read()?;
+            // Adopt the new size now so the next draw lays out (and clamps
+            // scroll offsets) at it, instead of painting over a stale buffer
+            if let Event::Resize(..) = event {
+                terminal.autoresize()?;
+            }
+            if let Event::Key(key) = event {
+                if key.kind == KeyEventKind::Press && app.overlay.is_some() {
+                    app.handle_overlay_key(key.code);
+                } else if key.kind == KeyEventKind::Press && app.log_search_input {
+                    app.handle_log_search_key(key.code);
+                } else if key.kind == KeyEventKind::Press && app.filter_input {
+                    app.handle_filter_key(key.code);
+                } else if key.kind == KeyEventKind::Press && app.command_input.is_some() {
+                    app.handle_command_key(key.code);
+                } else if key.kind == KeyEventKind::Press {
+                    let pending_g = std::mem::take(&mut app.pending_g);
+                    let pending_digits = std::mem::take(&mut app.pending_digits);
+                    let key = Key::from(key);
                     match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
-                        KeyCode::Char('j') | KeyCode::Down => app.select_next(),
-                        KeyCode::Char('k') | KeyCode::Up => app.select_prev(),
-                        KeyCode::Enter | KeyCode::Char('r') => {
-                            if app.go_to_selected() {
-                                app.should_quit = true;
-                            }
-                        }
-                        KeyCode::Char('R') => app.refresh_sessions(),
-                        KeyCode::Char('x') => app.kill_selected(),
-                        KeyCode::Char('D') | KeyCode::Char('d') => app.delete_selected(),
-                        KeyCode::Tab => app.toggle_view_mode(),
-                        // Number shortcuts 1-9
-                        KeyCode::Char(c @ '1'..='9') => {
-                            let idx = (c as usize) - ('1' as usize);
-                            if idx < app.sessions.len() {
-                                app.selected = idx;
-                                app.refresh_log();
+                        // While a log search is active, n/N step through its matches
+                        KeyCode::Char('n') if !key.ctrl && !app.log_search.is_empty() => app.step_log_match(true),
+                        KeyCode::Char('N') if !key.ctrl && !app.log_search.is_empty() => app.step_log_match(false),
+                        // Enter/Esc end a numeric jump rather than acting on it
+                        KeyCode::Enter | KeyCode::Esc if !pending_digits.is_empty() => {}
+                        // Number shortcuts: 1-9, or two digits once there are 10+ sessions
+                        KeyCode::Char(c @ '0'..='9') if !key.ctrl => app.push_jump_digit(pending_digits, c),
+                        _ => {
+                            if let Some(action) = app.keymap.action(key) {
+                                app.perform(action, pending_g);
                             }
                         }
-                        _ => {}
                     }
                 }
             }
         }
 
-        // Refresh sessions every 2s (heavy - process detection)
-        if last_session_tick.elapsed() >= session_tick_rate {
+        if let Some(path) = app.edit_path.take() {
+            if let Err(e) = open_in_editor(&mut terminal, &path) {
+                app.flash = Some(e.to_string());
+            }
             app.refresh_sessions();
-            last_session_tick = std::time::Instant::now();
         }
 
-        // Refresh log every 500ms (light - only if file changed)
+        // Refresh sessions every session_interval (heavy - process detection)
+        if last_session_tick.elapsed() >= session_tick_rate {
+            if !app.paused {
+                app.refresh_sessions();
+            }
+            last_session_tick = Instant::now();
+        }
+
+        // Escalate pending kills on time (also while paused), refreshing
+        // once one is gone rather than at the next session tick
+        if last_log_tick.elapsed() >= log_tick_rate && !app.pending_kills.is_empty() {
+            let pending = app.pending_kills.len();
+            app.check_pending_kills();
+            if app.pending_kills.len() < pending && !app.paused {
+                app.refresh_sessions();
+            }
+        }
+
+        // Refresh log every log_interval (light - only if file changed)
         if last_log_tick.elapsed() >= log_tick_rate {
-            app.refresh_log_if_changed(true);
-            last_log_tick = std::time::Instant::now();
+            if !app.paused {
+                app.refresh_log_if_changed(true);
+            }
+            last_log_tick = Instant::now();
         }
 
         if app.should_quit {
@@ -240,5 +1777,16 @@ fn main() -> io::Result<()> {
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
+    app.save_state();
+    if let Some(cmd) = app.exit_command {
+        println!("{}", cmd);
+    }
+    // Sent only now that the terminal is restored, so our own pane's shell gets it
+    if let Some(cmd) = app.here_command {
+        if !app.mux.run_in_current_pane(&cmd) {
+            println!("{}", cmd);
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::tmux::{self, TmuxLocation};
+use crate::zellij;
+
+/// Terminal multiplexer the sessions live in
+pub trait Multiplexer {
+    /// Short name for hints and notifications
+    fn name(&self) -> &'static str;
+
+    /// Mapping of shell PID -> pane location, for at least the given shells
+    fn pane_map(&self, shell_pids: &[u32]) -> HashMap<u32, TmuxLocation>;
+
+    /// Focus the pane at `location`, or say why it couldn't
+    fn switch_to(&self, location: &TmuxLocation) -> Result<(), String>;
+
+    /// Command to reach `location` by hand when `switch_to` can't, printed on exit
+    fn attach_command(&self, _location: &TmuxLocation) -> Option<String> {
+        None
+    }
+
+    /// Open a new window/tab running a shell command; false if nothing was opened
+    fn new_window_with_command(&self, window_name: &str, cmd: &str) -> bool;
+
+    /// Run a shell command in the current pane; false if the backend can't
+    fn run_in_current_pane(&self, _cmd: &str) -> bool {
+        false
+    }
+
+    /// Brief status message, if the backend has somewhere to show one
+    fn notify(&self, _msg: &str) {}
+
+    /// Close the window at `location`, if the backend supports it
+    fn kill_window(&self, _location: &TmuxLocation) {}
+
+    /// Last failure of a background command (pane listing, closing a window)
+    fn take_error(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether a real multiplexer is behind this backend
+    fn is_multiplexer(&self) -> bool {
+        true
+    }
+}
+
+/// Shell command that resumes a session from its project directory
+pub fn resume_command(config: &Config, project_path: &str, session_id: &str) -> String {
+    format!("{} --resume {}", claude_command(config, project_path), session_id)
+}
+
+/// Shell command that starts a fresh session in a project directory
+pub fn new_session_command(config: &Config, project_path: &str) -> String {
+    claude_command(config, project_path)
+}
+
+/// `cd <project> && <claude_bin> <claude_args>`
+fn claude_command(config: &Config, project_path: &str) -> String {
+    let mut cmd = format!("cd {} && {}", shell_quote(project_path), shell_quote(&config.claude_bin));
+    if !config.claude_args.trim().is_empty() {
+        cmd.push(' ');
+        cmd.push_str(config.claude_args.trim());
+    }
+    cmd
+}
+
+/// The user's shell from $SHELL, or `sh`
+pub fn user_shell() -> String {
+    std::env::var("SHELL").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| "sh".to_string())
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+pub struct TmuxBackend;
+
+impl Multiplexer for TmuxBackend {
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn pane_map(&self, _shell_pids: &[u32]) -> HashMap<u32, TmuxLocation> {
+        tmux::get_pane_map()
+    }
+
+    fn switch_to(&self, location: &TmuxLocation) -> Result<(), String> {
+        tmux::switch_to_window(location)
+    }
+
+    fn new_window_with_command(&self, window_name: &str, cmd: &str) -> bool {
+        tmux::new_window_with_command(window_name, cmd)
+    }
+
+    fn run_in_current_pane(&self, cmd: &str) -> bool {
+        match tmux::send_to_current_pane(cmd) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("claude-watch: tmux send-keys: {}", e);
+                false
+            }
+        }
+    }
+
+    fn notify(&self, msg: &str) {
+        tmux::notify(msg);
+    }
+
+    fn kill_window(&self, location: &TmuxLocation) {
+        tmux::kill_window(location);
+    }
+
+    fn take_error(&self) -> Option<String> {
+        tmux::take_error()
+    }
+}
+
+pub struct ZellijBackend;
+
+impl Multiplexer for ZellijBackend {
+    fn name(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn pane_map(&self, shell_pids: &[u32]) -> HashMap<u32, TmuxLocation> {
+        zellij::get_pane_map(shell_pids)
+    }
+
+    /// Zellij's CLI can't focus a pane by id, so this shows the pane's tab.
+    /// It can't switch the client to another Zellij session either.
+    fn switch_to(&self, location: &TmuxLocation) -> Result<(), String> {
+        if !in_current_zellij_session(location) {
+            return Err(format!("it's in Zellij session {}", location.session));
+        }
+        if location.window_index == 0 {
+            return Err("its tab wasn't found in the layout".to_string());
+        }
+        zellij::go_to_tab(location.window_index)
+    }
+
+    fn take_error(&self) -> Option<String> {
+        zellij::take_error()
+    }
+
+    fn attach_command(&self, location: &TmuxLocation) -> Option<String> {
+        (!in_current_zellij_session(location))
+            .then(|| format!("zellij attach {}", shell_quote(&location.session)))
+    }
+
+    fn new_window_with_command(&self, window_name: &str, cmd: &str) -> bool {
+        zellij::new_tab_with_command(window_name, cmd)
+    }
+}
+
+fn in_current_zellij_session(location: &TmuxLocation) -> bool {
+    std::env::var("ZELLIJ_SESSION_NAME").is_ok_and(|name| name == location.session)
+}
+
+/// No multiplexer: resume in a new `$TERMINAL` window when one is configured
+pub struct TerminalBackend;
+
+impl Multiplexer for TerminalBackend {
+    fn name(&self) -> &'static str {
+        "terminal"
+    }
+
+    /// A tmux server may still be running outside of it; show where sessions live
+    fn pane_map(&self, _shell_pids: &[u32]) -> HashMap<u32, TmuxLocation> {
+        tmux::get_pane_map()
+    }
+
+    fn switch_to(&self, _location: &TmuxLocation) -> Result<(), String> {
+        Err("no multiplexer".to_string())
+    }
+
+    /// Spawn `$TERMINAL -e $SHELL -lic <cmd>`; without `$TERMINAL` the caller
+    /// prints the command instead
+    fn new_window_with_command(&self, _window_name: &str, cmd: &str) -> bool {
+        let terminal = match std::env::var("TERMINAL") {
+            Ok(t) if !t.is_empty() => t,
+            _ => return false,
+        };
+        Command::new(terminal)
+            .args(["-e", &user_shell(), "-lic", cmd])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .is_ok()
+    }
+
+    fn is_multiplexer(&self) -> bool {
+        false
+    }
+}
+
+/// Pick the backend from the environment: `$ZELLIJ`, then `$TMUX`, else plain terminal
+pub fn detect() -> Box<dyn Multiplexer> {
+    if std::env::var_os("ZELLIJ").is_some() {
+        Box::new(ZellijBackend)
+    } else if std::env::var_os("TMUX").is_some() {
+        Box::new(TmuxBackend)
+    } else {
+        Box::new(TerminalBackend)
+    }
+}
@@ -1,11 +1,15 @@
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Mutex;
-use sysinfo::{ProcessRefreshKind, RefreshKind, System, Pid};
+use sysinfo::{ProcessRefreshKind, RefreshKind, System, Pid, Signal};
 
 // Constants
 const MAX_PARENT_WALK_DEPTH: usize = 10;
 const KNOWN_SHELLS: &[&str] = &["zsh", "bash", "fish", "sh", "dash", "ksh", "tcsh"];
+/// JavaScript runtimes Claude's CLI may be launched through
+const JS_RUNTIMES: &[&str] = &["node", "bun", "deno"];
+/// Path fragments identifying Claude's CLI entrypoint script
+const CLAUDE_ENTRYPOINTS: &[&str] = &["@anthropic-ai/claude-code", "claude/cli.js"];
 
 /// Represents a running Claude Code process
 #[derive(Debug, Clone)]
@@ -13,6 +17,10 @@ pub struct ClaudeProcess {
     pub pid: u32,
     pub cwd: Option<PathBuf>,
     pub cpu_usage: f32,
+    /// Resident memory in bytes
+    pub memory_bytes: u64,
+    /// Command line, program first
+    pub cmd: Vec<String>,
 }
 
 // Cache System instance to avoid expensive re-initialization
@@ -30,6 +38,7 @@ pub fn find_claude_processes() -> Vec<ClaudeProcess> {
                     .with_cmd(sysinfo::UpdateKind::Always)
                     .with_cwd(sysinfo::UpdateKind::Always)
                     .with_cpu()
+                    .with_memory()
             )
         )
     });
@@ -42,6 +51,7 @@ pub fn find_claude_processes() -> Vec<ClaudeProcess> {
             .with_cmd(sysinfo::UpdateKind::Always)
             .with_cwd(sysinfo::UpdateKind::Always)
             .with_cpu()
+            .with_memory()
     );
 
     // First pass: collect all Claude PIDs
@@ -79,23 +89,44 @@ pub fn find_claude_processes() -> Vec<ClaudeProcess> {
             pid: pid.as_u32(),
             cwd: proc.cwd().map(|p| p.to_path_buf()),
             cpu_usage: proc.cpu_usage(),
+            memory_bytes: proc.memory(),
+            cmd: proc.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect(),
         })
         .collect()
 }
 
 fn is_claude_process(proc: &sysinfo::Process) -> bool {
+    let cmd: Vec<String> = proc.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect();
+    is_claude_cmdline(&proc.name().to_string_lossy(), &cmd)
+}
+
+/// Whether a process name and command line belong to Claude: either the
+/// `claude` binary itself, or a JS runtime running Claude's CLI script
+/// (e.g. `node /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js`)
+fn is_claude_cmdline(name: &str, cmd: &[String]) -> bool {
     // Skip our own monitoring app
-    let name = proc.name().to_string_lossy();
     if name.contains("claude-watch") {
         return false;
     }
 
-    proc.cmd().first()
-        .map(|arg| {
-            let s = arg.to_string_lossy().to_lowercase();
-            s == "claude" || s.ends_with("/claude")
-        })
-        .unwrap_or(false)
+    let Some(first) = cmd.first() else { return false };
+    let program = first.to_lowercase();
+    let program = program.rsplit('/').next().unwrap_or(&program);
+    if program == "claude" {
+        return true;
+    }
+
+    JS_RUNTIMES.contains(&program)
+        && cmd[1..].iter().any(|arg| CLAUDE_ENTRYPOINTS.iter().any(|e| arg.contains(e)))
+}
+
+/// One step in a process's ancestry (for the process tree overlay)
+#[derive(Debug, Clone)]
+pub struct ProcessAncestor {
+    pub pid: u32,
+    pub name: String,
+    /// This is the ancestor `get_shell_pid` resolves to
+    pub is_shell: bool,
 }
 
 /// Get the parent shell PID for a Claude process by walking up the process tree
@@ -108,13 +139,7 @@ pub fn get_shell_pid(pid: u32) -> Option<u32> {
 
     for _ in 0..MAX_PARENT_WALK_DEPTH {
         let proc = system.process(current_pid)?;
-        let name = proc.name().to_string_lossy();
-
-        // Check if this is a known shell (exact match or path ending)
-        let name_lower = name.to_lowercase();
-        if KNOWN_SHELLS.iter().any(|shell| {
-            name_lower == *shell || name_lower.ends_with(&format!("/{}", shell))
-        }) {
+        if is_shell_name(&proc.name().to_string_lossy()) {
             return Some(current_pid.as_u32());
         }
 
@@ -123,3 +148,122 @@ pub fn get_shell_pid(pid: u32) -> Option<u32> {
     }
     None
 }
+
+/// Current working directory of any process seen by the last scan
+pub fn get_cwd(pid: u32) -> Option<PathBuf> {
+    let system_guard = SYSTEM.lock().unwrap();
+    let system = system_guard.as_ref()?;
+    system.process(Pid::from_u32(pid))?.cwd().map(|p| p.to_path_buf())
+}
+
+/// Walk the same path as `get_shell_pid`, recording every ancestor (Claude first)
+pub fn get_ancestry(pid: u32) -> Vec<ProcessAncestor> {
+    let system_guard = SYSTEM.lock().unwrap();
+    let system = match system_guard.as_ref() {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+
+    let mut ancestors = Vec::new();
+    let mut found_shell = false;
+    let mut current_pid = Some(Pid::from_u32(pid));
+
+    for _ in 0..MAX_PARENT_WALK_DEPTH {
+        let proc = match current_pid.and_then(|p| system.process(p)) {
+            Some(p) => p,
+            None => break,
+        };
+        let name = proc.name().to_string_lossy().to_string();
+        let is_shell = !found_shell && is_shell_name(&name);
+        found_shell |= is_shell;
+
+        ancestors.push(ProcessAncestor {
+            pid: proc.pid().as_u32(),
+            name,
+            is_shell,
+        });
+        current_pid = proc.parent();
+    }
+    ancestors
+}
+
+/// Check if a process name is a known shell (exact match or path ending)
+fn is_shell_name(name: &str) -> bool {
+    let name_lower = name.to_lowercase();
+    KNOWN_SHELLS.iter().any(|shell| {
+        name_lower == *shell || name_lower.ends_with(&format!("/{}", shell))
+    })
+}
+
+/// Terminate a process: SIGTERM (graceful), or SIGKILL when `force` is set.
+/// Platforms without SIGTERM (Windows) fall back to a hard kill.
+/// Returns whether the signal was delivered.
+pub fn terminate(pid: u32, force: bool) -> bool {
+    let mut system_guard = SYSTEM.lock().unwrap();
+    let system = system_guard.get_or_insert_with(System::new);
+
+    let pid = Pid::from_u32(pid);
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+    let proc = match system.process(pid) {
+        Some(p) => p,
+        None => return false,
+    };
+
+    if force {
+        return proc.kill();
+    }
+    proc.kill_with(Signal::Term).unwrap_or_else(|| proc.kill())
+}
+
+/// Check whether a process still exists (zombies count as gone)
+pub fn is_alive(pid: u32) -> bool {
+    let mut system_guard = SYSTEM.lock().unwrap();
+    let system = system_guard.get_or_insert_with(System::new);
+
+    let pid = Pid::from_u32(pid);
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid)
+        .map(|p| p.status() != sysinfo::ProcessStatus::Zombie)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmdline(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn matches_claude_binary() {
+        assert!(is_claude_cmdline("claude", &cmdline(&["claude"])));
+        assert!(is_claude_cmdline("claude", &cmdline(&["/home/me/.local/bin/claude", "--resume", "abc"])));
+    }
+
+    #[test]
+    fn matches_node_running_cli_script() {
+        let cmd = cmdline(&["node", "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js"]);
+        assert!(is_claude_cmdline("node", &cmd));
+        let cmd = cmdline(&["/usr/bin/node", "--no-warnings", "/opt/claude/cli.js", "--continue"]);
+        assert!(is_claude_cmdline("node", &cmd));
+        let cmd = cmdline(&["bun", "/home/me/.bun/install/global/node_modules/@anthropic-ai/claude-code/cli.js"]);
+        assert!(is_claude_cmdline("bun", &cmd));
+    }
+
+    #[test]
+    fn ignores_unrelated_node_and_other_programs() {
+        assert!(!is_claude_cmdline("node", &cmdline(&["node", "/srv/app/server.js"])));
+        // Mentioning the package outside a JS runtime isn't enough
+        let cmd = cmdline(&["vim", "/tmp/@anthropic-ai/claude-code/notes.md"]);
+        assert!(!is_claude_cmdline("vim", &cmd));
+        assert!(!is_claude_cmdline("claude-helper", &cmdline(&["claude-helper"])));
+        assert!(!is_claude_cmdline("claude", &[]));
+    }
+
+    #[test]
+    fn ignores_the_monitor_itself() {
+        assert!(!is_claude_cmdline("claude-watch", &cmdline(&["claude-watch"])));
+        assert!(!is_claude_cmdline("claude-watch", &cmdline(&["/usr/local/bin/claude"])));
+    }
+}
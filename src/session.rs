@@ -1,20 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 use serde::{Deserialize, Serialize};
 
-use crate::process::{find_claude_processes, get_shell_pid};
-use crate::tmux::{get_pane_map, TmuxLocation};
-
-// Historical session limit
-const HISTORY_LIMIT: usize = 20;
+use crate::config::{AttentionConfig, Config};
+use crate::process::{find_claude_processes, get_cwd, get_shell_pid, ClaudeProcess};
+use crate::mux::Multiplexer;
+use crate::tmux::TmuxLocation;
 
 // Constants
 const JSONL_LINES_TO_SCAN: usize = 100;
+const CWD_LINES_TO_SCAN: usize = 20;
 const RECENTLY_MODIFIED_THRESHOLD_SECS: f32 = 3.0;
 const STALE_FILE_AGE_SECS: f32 = 999.0;
-const MESSAGE_TRUNCATE_LEN: usize = 100;
+/// Quiet seconds after which a thinking-only turn is taken as abandoned
+const THINKING_GRACE_SECS: f32 = 300.0;
+/// Cap on stored message previews (cards cut them to their width when drawn)
+const MESSAGE_MAX_CHARS: usize = 2000;
+/// Last-message placeholder while the newest assistant output is reasoning
+const THINKING_MARKER: &str = "(thinking…)";
+const JUST_ENDED_THRESHOLD_SECS: u64 = 300;
+const PROJECT_META_CACHE_TTL: Duration = Duration::from_secs(30);
 
 /// Local slash commands that don't trigger Claude to think
 const LOCAL_COMMANDS: &[&str] = &[
@@ -23,12 +33,13 @@ const LOCAL_COMMANDS: &[&str] = &[
     "/pr-comments", "/review", "/status", "/terminal-setup", "/vim",
 ];
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionStatus {
     Thinking,
     Processing,
     Waiting,
+    #[default]
     Idle,
 }
 
@@ -43,7 +54,7 @@ impl std::fmt::Display for SessionStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Session {
     pub id: String,
     pub project_name: String,
@@ -54,7 +65,12 @@ pub struct Session {
     pub tmux_location: Option<TmuxLocation>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tmux_target: Option<String>,
+    /// tmux location is the last known one, not confirmed by the live pane map
+    #[serde(skip)]
+    pub tmux_stale: bool,
     pub cpu_usage: f32,
+    /// Resident memory of the process in bytes (0 when not running)
+    pub memory_bytes: u64,
     /// Seconds since last activity (JSONL modification)
     pub last_activity_secs: u64,
     /// Process ID (for killing)
@@ -71,9 +87,36 @@ pub struct Session {
     /// Creation timestamp (ISO format)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<String>,
-    /// Full path to the JSONL file (for deletion)
+    /// Full path to the JSONL file (for the log pane and deletion)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jsonl_path: Option<String>,
+    /// Last assistant turn requested a tool that hasn't run yet
+    pub pending_approval: bool,
+    /// Last tool result reported an error
+    pub has_error: bool,
+    /// Computed from the attention heuristics (see `needs_attention`)
+    pub needs_attention: bool,
+    /// Tokens summed over the whole JSONL
+    #[serde(flatten)]
+    pub usage: Usage,
+    /// Model of the most recent assistant message that names one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Checked-out branch of the project (running sessions in git repos only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Sub-agent session (only listed with sidechains shown)
+    pub is_sidechain: bool,
+    /// Not running, but written to within the last few minutes
+    pub just_ended: bool,
+    /// Projects directory the session was found under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projects_root: Option<String>,
+    /// Unfinished items in the most recent TodoWrite of the scanned tail
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_todos: Option<u32>,
+    /// Historical session whose project directory is gone (can't be resumed)
+    pub project_missing: bool,
 }
 
 /// Entry from sessions-index.json
@@ -83,20 +126,21 @@ struct SessionIndexEntry {
     session_id: String,
     full_path: String,
     first_prompt: Option<String>,
-    message_count: u32,
+    message_count: Option<u32>,
     created: String,
     modified: String,
-    project_path: String,
+    /// Missing in some indexes; recovered from the directory name
+    project_path: Option<String>,
     #[serde(default)]
     is_sidechain: bool,
 }
 
-/// Container for sessions-index.json
+/// Container for sessions-index.json. Entries stay raw so one malformed
+/// entry doesn't take the rest of the file down with it.
 #[derive(Debug, Deserialize)]
 struct SessionIndex {
-    #[allow(dead_code)]
-    version: u32,
-    entries: Vec<SessionIndexEntry>,
+    #[serde(default)]
+    entries: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -107,173 +151,659 @@ struct JsonlMessage {
     #[allow(dead_code)]
     msg_type: Option<String>,
     message: Option<MessageContent>,
+    cwd: Option<String>,
+    /// Injected context (e.g. local-command caveats), not typed by the user
+    #[serde(rename = "isMeta", default)]
+    is_meta: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct MessageContent {
+    /// API message id, repeated on each line the message is split over
+    id: Option<String>,
     role: Option<String>,
     content: Option<serde_json::Value>,
+    usage: Option<Usage>,
+    model: Option<String>,
 }
 
-/// Get all active Claude sessions
-pub fn get_sessions() -> Vec<Session> {
-    let mut processes = find_claude_processes();
-    let pane_map = get_pane_map();
-
-    // Sort processes by PID (descending) for consistent JSONL assignment
-    // Higher PIDs with ongoing activity tend to have most recent JSONL
-    processes.sort_by(|a, b| b.pid.cmp(&a.pid));
+/// Token usage reported on assistant messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+}
 
-    let claude_dir = match dirs::home_dir() {
-        Some(h) => h.join(".claude").join("projects"),
-        None => return Vec::new(),
-    };
+impl Usage {
+    /// All input tokens: fresh, read from the cache, and written to it
+    pub fn all_input(&self) -> u64 {
+        self.input_tokens + self.cache_read_input_tokens + self.cache_creation_input_tokens
+    }
+}
 
-    if !claude_dir.exists() {
-        return Vec::new();
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
     }
+}
 
-    // Build dir_name -> project_path map
-    let mut project_dirs: HashMap<String, PathBuf> = HashMap::new();
-    if let Ok(entries) = fs::read_dir(&claude_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                    project_dirs.insert(dir_name.to_string(), path);
+/// Usage summed over a whole JSONL, read a bit further on each refresh
+#[derive(Debug, Default)]
+struct UsageTally {
+    /// Bytes of complete lines read so far
+    offset: u64,
+    /// Messages finished before `current`
+    total: Usage,
+    /// Message whose lines are still being read, with its latest usage
+    /// (each line of a split message repeats it, so it's counted once)
+    current: Option<(String, Usage)>,
+    model: Option<String>,
+}
+
+impl UsageTally {
+    fn add_line(&mut self, line: &[u8]) {
+        let Some(message) = serde_json::from_slice::<JsonlMessage>(line).ok().and_then(|m| m.message) else {
+            return;
+        };
+        if message.model.is_some() {
+            self.model = message.model;
+        }
+        let Some(usage) = message.usage else { return };
+        match (&mut self.current, message.id) {
+            (Some((current_id, current_usage)), Some(id)) if *current_id == id => *current_usage = usage,
+            (_, id) => {
+                if let Some((_, done)) = self.current.take() {
+                    self.total += done;
+                }
+                match id {
+                    Some(id) => self.current = Some((id, usage)),
+                    None => self.total += usage,
                 }
             }
         }
     }
 
-    let mut sessions = Vec::new();
+    fn usage(&self) -> Usage {
+        let mut usage = self.total;
+        if let Some((_, current)) = self.current {
+            usage += current;
+        }
+        usage
+    }
+}
 
-    // Track how many processes we've seen per project (for JSONL file assignment)
-    let mut project_process_index: HashMap<String, usize> = HashMap::new();
+/// Metadata derived from a project path, cached between refreshes
+#[derive(Debug, Clone)]
+struct ProjectMeta {
+    fetched_at: Instant,
+    project_name: String,
+    git_branch: Option<String>,
+}
 
-    // Iterate over PROCESSES (not project dirs) to support multiple sessions per directory
-    for process in &processes {
-        let cwd = match &process.cwd {
-            Some(c) => c.to_string_lossy().to_string(),
-            None => continue,
-        };
+// Per project path; stale after PROJECT_META_CACHE_TTL (not on JSONL writes,
+// which an active session makes every refresh)
+static PROJECT_META_CACHE: Mutex<Option<HashMap<String, ProjectMeta>>> = Mutex::new(None);
 
-        let dir_name = convert_path_to_dir_name(&cwd);
+// Shared git dir per project path; stale after PROJECT_META_CACHE_TTL, so a
+// repo created or removed while we run is picked up
+static REPO_DIR_CACHE: Mutex<Option<HashMap<String, RepoDir>>> = Mutex::new(None);
 
-        // Find matching project directory
-        let project_dir = match project_dirs.get(&dir_name) {
-            Some(p) => p,
-            None => continue,
-        };
+struct RepoDir {
+    fetched_at: Instant,
+    dir: Option<String>,
+}
+
+// First prompt per JSONL path; only found prompts are kept, since a new
+// session may not have written one yet
+static FIRST_PROMPT_CACHE: Mutex<Option<HashMap<PathBuf, String>>> = Mutex::new(None);
+
+// Last message per historical JSONL path, kept until the file's mtime changes
+static LAST_MESSAGE_CACHE: Mutex<Option<HashMap<PathBuf, CachedMessage>>> = Mutex::new(None);
+
+struct CachedMessage {
+    modified: SystemTime,
+    message: Option<String>,
+}
+
+// Token usage per JSONL path, so each refresh only reads appended lines
+static USAGE_TALLIES: Mutex<Option<HashMap<PathBuf, UsageTally>>> = Mutex::new(None);
+
+// sessions-index.json files already reported as damaged
+static WARNED_INDEXES: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+// Damaged-index reports not yet picked up by `take_index_warnings`
+static INDEX_WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Counts from the last `get_sessions` scan, for the --debug pane
+#[derive(Debug, Clone, Default)]
+pub struct ScanDiagnostics {
+    /// Claude processes found (sub-agents excluded)
+    pub processes: usize,
+    /// Processes matched to a project directory
+    pub matched_projects: usize,
+    /// Processes that ended up with a parsed JSONL session
+    pub parsed_sessions: usize,
+    /// Sessions located in a multiplexer pane
+    pub mapped_panes: usize,
+    /// Panes listed by the multiplexer
+    pub panes_seen: usize,
+    /// Project directories found under all roots
+    pub project_dirs: usize,
+}
+
+static LAST_SCAN: Mutex<Option<ScanDiagnostics>> = Mutex::new(None);
+
+/// Diagnostics from the most recent scan, if one has run
+pub fn last_scan_diagnostics() -> Option<ScanDiagnostics> {
+    LAST_SCAN.lock().unwrap().clone()
+}
+
+/// JSONL name prefixes that aren't sessions, from the config (set once at startup)
+static EXCLUDE_JSONL_PREFIXES: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Override which JSONL name prefixes are skipped (default `agent-`)
+pub fn set_exclude_jsonl_prefixes(prefixes: &[String]) {
+    let _ = EXCLUDE_JSONL_PREFIXES.set(prefixes.to_vec());
+}
+
+/// Whether `path` is a session's JSONL file rather than a sub-agent's or
+/// some other tool's (see `exclude_jsonl_prefixes`)
+pub fn is_session_jsonl(path: &Path) -> bool {
+    let prefixes = EXCLUDE_JSONL_PREFIXES.get_or_init(|| vec!["agent-".to_string()]);
+    is_jsonl_without_prefix(path, prefixes)
+}
+
+fn is_jsonl_without_prefix(path: &Path, prefixes: &[String]) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    path.extension().is_some_and(|ext| ext == "jsonl")
+        && !prefixes.iter().any(|p| name.starts_with(p.as_str()))
+}
+
+/// Projects directories from --projects-dir (set once at startup)
+static PROJECTS_DIR_OVERRIDE: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// Override the projects directories for the rest of the process.
+/// Each value may itself be a colon-separated list.
+pub fn set_projects_dirs(values: &[&str]) {
+    let dirs = values.iter()
+        .flat_map(|v| v.split(':'))
+        .filter(|p| !p.is_empty())
+        .map(expand_path)
+        .collect();
+    let _ = PROJECTS_DIR_OVERRIDE.set(dirs);
+}
+
+/// Why sessions couldn't be looked up at all (as opposed to there being none)
+#[derive(Debug, Clone, PartialEq)]
+pub enum LookupError {
+    /// No --projects-dir or $CLAUDE_CONFIG_DIR, and no home directory to fall back on
+    NoHomeDir,
+}
+
+impl std::fmt::Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::NoHomeDir => write!(
+                f,
+                "could not determine home directory (set CLAUDE_CONFIG_DIR or pass --projects-dir)",
+            ),
+        }
+    }
+}
+
+/// Resolve the Claude projects directories, in order of precedence:
+/// 1. --projects-dir flags
+/// 2. <dir>/projects for each entry of a colon-separated $CLAUDE_CONFIG_DIR
+/// 3. ~/.claude/projects
+pub fn resolve_projects_dirs() -> Result<Vec<PathBuf>, LookupError> {
+    if let Some(dirs) = PROJECTS_DIR_OVERRIDE.get() {
+        return Ok(dirs.clone());
+    }
+    if let Ok(config_dirs) = std::env::var("CLAUDE_CONFIG_DIR") {
+        let dirs: Vec<PathBuf> = config_dirs.split(':')
+            .filter(|d| !d.is_empty())
+            .map(|d| expand_path(d).join("projects"))
+            .collect();
+        if !dirs.is_empty() {
+            return Ok(dirs);
+        }
+    }
+    dirs::home_dir()
+        .map(|h| vec![h.join(".claude").join("projects")])
+        .ok_or(LookupError::NoHomeDir)
+}
+
+/// The projects directories, or none when they can't be resolved
+/// (see `resolve_projects_dirs` to tell the two apart)
+pub fn projects_dirs() -> Vec<PathBuf> {
+    resolve_projects_dirs().unwrap_or_default()
+}
+
+/// Every project directory under every projects root
+fn project_subdirs() -> impl Iterator<Item = PathBuf> {
+    projects_dirs().into_iter()
+        .filter_map(|root| fs::read_dir(root).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+}
 
-        // Get index for this process (0 = most recent JSONL, 1 = second, etc.)
-        let jsonl_index = *project_process_index.get(&dir_name).unwrap_or(&0);
-        project_process_index.insert(dir_name.clone(), jsonl_index + 1);
+/// Expand a leading `~` and make relative paths absolute
+fn expand_path(path: &str) -> PathBuf {
+    let expanded = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs::home_dir()
+            .map(|h| h.join(rest.trim_start_matches('/')))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        _ => PathBuf::from(path),
+    };
+
+    if expanded.is_relative() {
+        if let Ok(cwd) = std::env::current_dir() {
+            return cwd.join(expanded);
+        }
+    }
+    expanded
+}
+
+/// Get all active Claude sessions
+pub fn get_sessions(mux: &dyn Multiplexer, options: &StatusOptions) -> Vec<Session> {
+    let sessions = running_sessions(mux, options);
+    retain_usage_tallies(&sessions);
+    sessions
+}
+
+/// Running sessions, leaving token tallies of unlisted files in place
+fn running_sessions(mux: &dyn Multiplexer, options: &StatusOptions) -> Vec<Session> {
+    let mut processes = find_claude_processes();
+    let shell_pids: Vec<u32> = processes.iter().filter_map(|p| get_shell_pid(p.pid)).collect();
+    let pane_map = mux.pane_map(&shell_pids);
+
+    // Sort processes by PID (descending) for consistent JSONL assignment
+    // (see `assign_jsonl_files`)
+    processes.sort_by_key(|p| std::cmp::Reverse(p.pid));
+
+    // Build dir_name -> project directories map (one per root that has it)
+    let mut project_dirs: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in project_subdirs() {
+        if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
+            project_dirs.entry(dir_name.to_string()).or_default().push(path.clone());
+        }
+    }
 
-        // Find tmux location for this process
-        let tmux_location = get_shell_pid(process.pid)
-            .and_then(|shell_pid| pane_map.get(&shell_pid).cloned());
+    // cwd recorded in each project's newest JSONL, built only if some process needs it
+    let mut jsonl_cwds = None;
+
+    // Group PROCESSES (not project dirs) by project to support multiple sessions per directory
+    let mut project_processes: HashMap<String, Vec<(&ClaudeProcess, String)>> = HashMap::new();
+    for process in &processes {
+        if let Some((dir_name, cwd)) = match_project(process, &project_dirs, &mut jsonl_cwds) {
+            project_processes.entry(dir_name).or_default().push((process, cwd));
+        }
+    }
 
-        // Parse the Nth most recent JSONL file
-        if let Some(session) = parse_project_session(project_dir, &cwd, tmux_location, process.cpu_usage, jsonl_index, process.pid) {
-            sessions.push(session);
+    let mut sessions = Vec::new();
+    for (dir_name, group) in &project_processes {
+        let jsonl_files = jsonl_files_by_recency(&project_dirs[dir_name]);
+        let known_ids: Vec<Option<String>> = group.iter()
+            .map(|(process, _)| session_id_from_args(&process.cmd))
+            .collect();
+        let assigned = assign_jsonl_files(&known_ids, &jsonl_files);
+
+        for ((process, cwd), file) in group.iter().zip(assigned) {
+            let Some((jsonl_path, modified_time)) = file else { continue };
+
+            // Find tmux location for this process
+            let tmux_location = get_shell_pid(process.pid)
+                .and_then(|shell_pid| pane_map.get(&shell_pid).cloned());
+
+            if let Some(mut session) = parse_project_session(jsonl_path, *modified_time, cwd, tmux_location, process.cpu_usage, process.pid, options) {
+                session.memory_bytes = process.memory_bytes;
+                sessions.push(session);
+            }
         }
     }
 
-    // Sort by tmux location (session:window) for stable order
-    sessions.sort_by(|a, b| {
-        a.tmux_target.cmp(&b.tmux_target)
+    // Two roots can hold copies of the same session file
+    let mut seen_ids = HashSet::new();
+    sessions.retain(|s| seen_ids.insert(s.id.clone()));
+
+    // Sort by tmux location (session:window), then newest PID, for stable order
+    sessions.sort_by(|a, b| a.tmux_target.cmp(&b.tmux_target).then(b.pid.cmp(&a.pid)));
+
+    *LAST_SCAN.lock().unwrap() = Some(ScanDiagnostics {
+        processes: processes.len(),
+        matched_projects: project_processes.values().map(Vec::len).sum(),
+        parsed_sessions: sessions.len(),
+        mapped_panes: sessions.iter().filter(|s| s.tmux_location.is_some()).count(),
+        panes_seen: pane_map.len(),
+        project_dirs: project_dirs.values().map(Vec::len).sum(),
     });
 
     sessions
 }
 
-/// Get all sessions (running + historical from sessions-index.json)
-pub fn get_all_sessions() -> Vec<Session> {
+/// Match a process to a project directory name and the project path to
+/// show for it, trying in order:
+/// 1. the process's cwd, converted to a directory name
+/// 2. the cwd of its parent shell (for wrappers that start Claude elsewhere)
+/// 3. the project whose newest JSONL records the process's cwd in its `cwd`
+///    field (paths the name conversion doesn't reproduce)
+fn match_project(
+    process: &ClaudeProcess,
+    project_dirs: &HashMap<String, Vec<PathBuf>>,
+    jsonl_cwds: &mut Option<HashMap<String, String>>,
+) -> Option<(String, String)> {
+    let cwd = process.cwd.as_ref()?.to_string_lossy().to_string();
+    let dir_name = convert_path_to_dir_name(&cwd);
+    if project_dirs.contains_key(&dir_name) {
+        return Some((dir_name, cwd));
+    }
+
+    if let Some(shell_cwd) = get_shell_pid(process.pid).and_then(get_cwd) {
+        let shell_cwd = shell_cwd.to_string_lossy().to_string();
+        let dir_name = convert_path_to_dir_name(&shell_cwd);
+        if project_dirs.contains_key(&dir_name) {
+            return Some((dir_name, shell_cwd));
+        }
+    }
+
+    let jsonl_cwds = jsonl_cwds.get_or_insert_with(|| recent_jsonl_cwds(project_dirs));
+    let dir_name = jsonl_cwds.get(&cwd)?.clone();
+    Some((dir_name, cwd))
+}
+
+/// Map the last `cwd` recorded in each project's newest JSONL to its directory name
+fn recent_jsonl_cwds(project_dirs: &HashMap<String, Vec<PathBuf>>) -> HashMap<String, String> {
+    project_dirs.iter()
+        .filter_map(|(dir_name, dirs)| {
+            let (newest, _) = jsonl_files_by_recency(dirs).into_iter().next()?;
+            let cwd = read_last_lines(&newest, CWD_LINES_TO_SCAN)?.iter().rev()
+                .filter_map(|line| serde_json::from_str::<JsonlMessage>(line).ok())
+                .find_map(|msg| msg.cwd)?;
+            Some((cwd, dir_name.clone()))
+        })
+        .collect()
+}
+
+/// Get all sessions (running + the `history_limit` most recent historical ones;
+/// a limit of 0 means unlimited)
+pub fn get_all_sessions(
+    mux: &dyn Multiplexer,
+    options: &StatusOptions,
+    history_limit: usize,
+    include_sidechains: bool,
+) -> Vec<Session> {
     // Start with running sessions
-    let running_sessions = get_sessions();
-    let running_ids: std::collections::HashSet<String> = running_sessions.iter()
+    let running_sessions = running_sessions(mux, options);
+    let running_ids: HashSet<String> = running_sessions.iter()
         .map(|s| s.id.clone())
         .collect();
 
-    let claude_dir = match dirs::home_dir() {
-        Some(h) => h.join(".claude").join("projects"),
-        None => return running_sessions,
-    };
+    // Collect historical sessions from all sessions-index.json files
+    let mut historical = get_historical_sessions(&running_ids, include_sidechains);
+    remove_running_duplicates(&running_sessions, &mut historical);
+
+    // Sort historical by recency (most recent first)
+    historical.sort_by_key(|s| s.last_activity_secs);
 
-    if !claude_dir.exists() {
-        return running_sessions;
+    // Take only the most recent history_limit
+    if history_limit > 0 {
+        historical.truncate(history_limit);
     }
 
-    // Collect historical sessions from all sessions-index.json files
+    // Tail-parse the last message and tally tokens only for the sessions we
+    // keep (both cached per file, since history rarely changes between
+    // refreshes), and flag sessions whose process exited recently
+    for session in &mut historical {
+        session.just_ended = session.last_activity_secs < JUST_ENDED_THRESHOLD_SECS;
+        if let Some(ref path) = session.jsonl_path {
+            (session.usage, session.model) = jsonl_usage(Path::new(path));
+        }
+        session.last_message = session.jsonl_path.as_deref()
+            .and_then(|p| cached_last_message(&PathBuf::from(p)))
+            .or_else(|| session.first_prompt.clone());
+    }
+
+    // Combine: running first, then historical
+    let mut all_sessions = running_sessions;
+    all_sessions.extend(historical);
+    retain_usage_tallies(&all_sessions);
+
+    all_sessions
+}
+
+/// Ways a session's conversation can be named: its id and its JSONL file
+/// name, both trimmed and lowercased. The running side takes its id from
+/// the JSONL contents, which can disagree with (or be missing from) the
+/// index, while the file name stays the same.
+fn session_keys(session: &Session) -> Vec<String> {
+    let stem = session.jsonl_path.as_deref()
+        .and_then(|p| Path::new(p).file_stem())
+        .map(|s| s.to_string_lossy().to_string());
+    std::iter::once(session.id.clone())
+        .chain(stem)
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .collect()
+}
+
+/// Drop historical entries for sessions that are already listed as running
+fn remove_running_duplicates(running: &[Session], historical: &mut Vec<Session>) {
+    let running_keys: HashSet<String> = running.iter().flat_map(session_keys).collect();
+    historical.retain(|s| !session_keys(s).iter().any(|k| running_keys.contains(k)));
+}
+
+/// Collect every non-running session from the sessions-index.json files
+/// (unsorted), skipping sub-agent sidechains unless `include_sidechains`
+fn get_historical_sessions(running_ids: &HashSet<String>, include_sidechains: bool) -> Vec<Session> {
     let mut historical: Vec<Session> = Vec::new();
+    // The same session can be indexed under more than one root
+    let mut seen_ids: HashSet<String> = HashSet::new();
 
-    if let Ok(entries) = fs::read_dir(&claude_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
+    for path in project_subdirs() {
+        let index_path = path.join("sessions-index.json");
+        if !index_path.exists() {
+            continue;
+        }
+
+        // Parse sessions-index.json, tolerating bad entries and
+        // falling back to the JSONL files when it's unreadable
+        let content = fs::read_to_string(&index_path).unwrap_or_default();
+        let index_entries = match parse_session_index(&content) {
+            Some((entries, 0)) => entries,
+            Some((entries, skipped)) => {
+                warn_index_once(&index_path, &format!("skipped {} unreadable entries", skipped));
+                entries
+            }
+            None => {
+                warn_index_once(&index_path, "unreadable, listing its JSONL files instead");
+                scan_jsonl_entries(&path)
             }
+        };
 
-            let index_path = path.join("sessions-index.json");
-            if !index_path.exists() {
+        for entry in index_entries {
+            // Skip sidechains (unless requested) and already-running sessions
+            if (entry.is_sidechain && !include_sidechains)
+                || running_ids.contains(&entry.session_id)
+                || !seen_ids.insert(entry.session_id.clone())
+            {
                 continue;
             }
 
-            // Parse sessions-index.json
-            if let Ok(content) = fs::read_to_string(&index_path) {
-                if let Ok(index) = serde_json::from_str::<SessionIndex>(&content) {
-                    for entry in index.entries {
-                        // Skip sidechains and already-running sessions
-                        if entry.is_sidechain || running_ids.contains(&entry.session_id) {
-                            continue;
-                        }
+            // Calculate age from modified timestamp
+            let last_activity_secs = parse_iso_age(&entry.modified);
+
+            let project_path = entry.project_path.unwrap_or_else(|| {
+                let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                convert_dir_name_to_path(dir_name)
+            });
+            let project_name = project_name(&project_path);
+            let project_missing = !Path::new(&project_path).is_dir();
+
+            historical.push(Session {
+                id: entry.session_id,
+                project_name,
+                project_path,
+                status: SessionStatus::Idle,
+                last_message: None,
+                tmux_location: None,
+                tmux_target: None,
+                tmux_stale: false,
+                cpu_usage: 0.0,
+                memory_bytes: 0,
+                last_activity_secs,
+                pid: None,
+                is_running: false,
+                first_prompt: entry.first_prompt,
+                message_count: entry.message_count,
+                created_at: Some(entry.created),
+                jsonl_path: Some(entry.full_path),
+                pending_approval: false,
+                has_error: false,
+                needs_attention: false,
+                usage: Usage::default(),
+                model: None,
+                pending_todos: None,
+                git_branch: None,
+                is_sidechain: entry.is_sidechain,
+                just_ended: false,
+                projects_root: path.parent().map(|r| r.to_string_lossy().to_string()),
+                project_missing,
+            });
+        }
+    }
 
-                        // Calculate age from modified timestamp
-                        let last_activity_secs = parse_iso_age(&entry.modified);
-
-                        // Extract project name from path
-                        let project_name = entry.project_path
-                            .split('/')
-                            .filter(|s| !s.is_empty())
-                            .last()
-                            .unwrap_or("Unknown")
-                            .to_string();
-
-                        historical.push(Session {
-                            id: entry.session_id,
-                            project_name,
-                            project_path: entry.project_path,
-                            status: SessionStatus::Idle,
-                            last_message: entry.first_prompt.clone(),
-                            tmux_location: None,
-                            tmux_target: None,
-                            cpu_usage: 0.0,
-                            last_activity_secs,
-                            pid: None,
-                            is_running: false,
-                            first_prompt: entry.first_prompt,
-                            message_count: Some(entry.message_count),
-                            created_at: Some(entry.created),
-                            jsonl_path: Some(entry.full_path),
-                        });
-                    }
-                }
+    historical
+}
+
+/// Parse sessions-index.json, skipping entries that don't deserialize.
+/// Returns the good entries and how many were skipped, or None when the
+/// file as a whole is unparseable (e.g. a truncated write).
+fn parse_session_index(content: &str) -> Option<(Vec<SessionIndexEntry>, usize)> {
+    let index = serde_json::from_str::<SessionIndex>(content).ok()?;
+    let total = index.entries.len();
+    let entries: Vec<SessionIndexEntry> = index.entries.into_iter()
+        .filter_map(|e| serde_json::from_value(e).ok())
+        .collect();
+    let skipped = total - entries.len();
+    Some((entries, skipped))
+}
+
+/// Index entries built from a project directory's JSONL files, for when
+/// its sessions-index.json can't be read
+fn scan_jsonl_entries(project_dir: &Path) -> Vec<SessionIndexEntry> {
+    let Ok(entries) = fs::read_dir(project_dir) else { return Vec::new() };
+
+    entries.flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            if !is_session_jsonl(&path) {
+                return None;
             }
-        }
+            let metadata = e.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            let created = metadata.created().unwrap_or(modified);
+            let to_iso = |t: SystemTime| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339();
+
+            Some(SessionIndexEntry {
+                session_id: path.file_stem()?.to_str()?.to_string(),
+                full_path: path.to_string_lossy().to_string(),
+                first_prompt: None,
+                message_count: None,
+                created: to_iso(created),
+                modified: to_iso(modified),
+                project_path: read_cwd(&path),
+                is_sidechain: false,
+            })
+        })
+        .collect()
+}
+
+/// Working directory recorded near the start of a JSONL file
+fn read_cwd(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    BufReader::new(file).lines()
+        .take(JSONL_LINES_TO_SCAN)
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<JsonlMessage>(&line).ok())
+        .find_map(|msg| msg.cwd)
+}
+
+/// The first thing the user typed in a session, from the head of its JSONL
+/// (the tail read for status never reaches it in a long session)
+fn read_first_prompt(path: &Path) -> Option<String> {
+    let mut cache = FIRST_PROMPT_CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some(prompt) = cache.get(path) {
+        return Some(prompt.clone());
     }
 
-    // Sort historical by recency (most recent first)
-    historical.sort_by(|a, b| a.last_activity_secs.cmp(&b.last_activity_secs));
+    let file = File::open(path).ok()?;
+    let prompt = BufReader::new(file).lines()
+        .take(JSONL_LINES_TO_SCAN)
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<JsonlMessage>(&line).ok())
+        .filter(|msg| !msg.is_meta)
+        .filter_map(|msg| msg.message)
+        .filter(|m| m.role.as_deref() == Some("user"))
+        .filter_map(|m| m.content)
+        .filter(|c| !check_local_command(c))
+        .find_map(|c| extract_text(&c))
+        .map(truncate_message)?;
+    cache.insert(path.to_path_buf(), prompt.clone());
+    Some(prompt)
+}
+
+/// Record a damaged sessions-index.json, once per file
+fn warn_index_once(index_path: &Path, problem: &str) {
+    let mut warned_guard = WARNED_INDEXES.lock().unwrap();
+    let warned = warned_guard.get_or_insert_with(HashSet::new);
+    if warned.insert(index_path.to_path_buf()) {
+        INDEX_WARNINGS.lock().unwrap().push(format!("{}: {}", index_path.display(), problem));
+    }
+}
 
-    // Take only the most recent HISTORY_LIMIT
-    historical.truncate(HISTORY_LIMIT);
+/// Damaged-index reports since the last call; the TUI shows them, the CLI prints them
+pub fn take_index_warnings() -> Vec<String> {
+    std::mem::take(&mut *INDEX_WARNINGS.lock().unwrap())
+}
 
-    // Combine: running first, then historical
-    let mut all_sessions = running_sessions;
-    all_sessions.extend(historical);
+/// Historical sessions last modified more than `max_age_secs` ago, oldest first
+pub fn get_prune_candidates(mux: &dyn Multiplexer, options: &StatusOptions, max_age_secs: u64) -> Vec<Session> {
+    let running_ids: HashSet<String> = running_sessions(mux, options).into_iter()
+        .map(|s| s.id)
+        .collect();
 
-    all_sessions
+    let mut candidates: Vec<Session> = get_historical_sessions(&running_ids, false)
+        .into_iter()
+        .filter(|s| s.last_activity_secs > max_age_secs)
+        .collect();
+    candidates.sort_by_key(|s| std::cmp::Reverse(s.last_activity_secs));
+    candidates
+}
+
+/// Combine the enabled attention heuristics into a single "needs me" flag
+pub fn needs_attention(session: &Session, config: &AttentionConfig) -> bool {
+    if !session.is_running {
+        return false;
+    }
+
+    let working = matches!(session.status, SessionStatus::Thinking | SessionStatus::Processing);
+    let stalled = config.stall_secs > 0 && working && session.last_activity_secs >= config.stall_secs;
+
+    (config.waiting && session.status == SessionStatus::Waiting)
+        || (config.pending_approval && session.pending_approval)
+        || (config.errors && session.has_error)
+        || stalled
 }
 
 /// Delete a session's JSONL file and remove from sessions-index.json
@@ -299,6 +829,11 @@ pub fn delete_session(session: &Session) {
     }
 }
 
+/// Seconds since a historical session was created, when the index records it
+pub fn created_age_secs(session: &Session) -> Option<u64> {
+    session.created_at.as_deref().map(parse_iso_age)
+}
+
 /// Parse ISO timestamp and return seconds ago
 fn parse_iso_age(iso_str: &str) -> u64 {
     use chrono::{DateTime, Utc};
@@ -312,51 +847,93 @@ fn parse_iso_age(iso_str: &str) -> u64 {
     }
 }
 
-fn parse_project_session(
-    project_dir: &PathBuf,
-    project_path: &str,
-    tmux_location: Option<TmuxLocation>,
-    cpu_usage: f32,
-    jsonl_index: usize,
-    pid: u32,
-) -> Option<Session> {
-    // Find JSONL files sorted by modification time (excluding agent-*.jsonl)
-    let mut jsonl_files: Vec<_> = fs::read_dir(project_dir).ok()?
-        .flatten()
-        .filter(|e| {
-            let path = e.path();
-            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            path.extension().map(|ext| ext == "jsonl").unwrap_or(false)
-                && !name.starts_with("agent-")
+/// Session id a Claude command line pins itself to, from `--session-id <id>`
+/// or `--resume <id>` / `-r <id>` (both also accepted as `--flag=<id>`)
+fn session_id_from_args(cmd: &[String]) -> Option<String> {
+    let mut args = cmd.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some((flag, value)) = arg.split_once('=') {
+            if matches!(flag, "--session-id" | "--resume") && !value.is_empty() {
+                return Some(value.to_string());
+            }
+        } else if matches!(arg.as_str(), "--session-id" | "--resume" | "-r") {
+            // A bare --resume opens the picker; its next arg may be another flag
+            return args.next().filter(|v| !v.starts_with('-')).cloned();
+        }
+    }
+    None
+}
+
+/// Pick a JSONL file for each of a project's processes. A process whose
+/// session id is known from its command line gets that exact file; the
+/// rest take the remaining files in recency order. Processes arrive sorted
+/// by descending PID, assuming newer processes wrote more recently; when
+/// two unidentified sessions in one directory break that assumption, their
+/// logs are swapped until the other one writes again.
+fn assign_jsonl_files<'a>(
+    known_ids: &[Option<String>],
+    jsonl_files: &'a [(PathBuf, SystemTime)],
+) -> Vec<Option<&'a (PathBuf, SystemTime)>> {
+    let file_id = |path: &Path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string);
+
+    let mut assigned: Vec<Option<&(PathBuf, SystemTime)>> = known_ids.iter()
+        .map(|id| {
+            let id = id.as_deref()?;
+            jsonl_files.iter().find(|(path, _)| file_id(path).as_deref() == Some(id))
         })
+        .collect();
+
+    let unclaimed: Vec<&(PathBuf, SystemTime)> = jsonl_files.iter()
+        .filter(|file| !assigned.iter().any(|a| a.is_some_and(|a| std::ptr::eq(a, *file))))
+        .collect();
+    let mut unclaimed = unclaimed.into_iter();
+    for slot in assigned.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = unclaimed.next();
+    }
+    assigned
+}
+
+/// Session JSONL files across the given project directories, most
+/// recently modified first
+fn jsonl_files_by_recency(project_dirs: &[PathBuf]) -> Vec<(PathBuf, SystemTime)> {
+    let mut jsonl_files: Vec<_> = project_dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|e| is_session_jsonl(&e.path()))
         .filter_map(|e| {
             let modified = e.metadata().and_then(|m| m.modified()).ok()?;
             Some((e.path(), modified))
         })
         .collect();
 
-    jsonl_files.sort_by(|a, b| b.1.cmp(&a.1));
-
-    // Pick the Nth most recent JSONL file
-    let (jsonl_path, modified_time) = jsonl_files.get(jsonl_index)?;
+    jsonl_files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    jsonl_files
+}
 
+fn parse_project_session(
+    jsonl_path: &PathBuf,
+    modified_time: SystemTime,
+    project_path: &str,
+    tmux_location: Option<TmuxLocation>,
+    cpu_usage: f32,
+    pid: u32,
+    options: &StatusOptions,
+) -> Option<Session> {
     // Check if file was recently modified
-    let file_age = std::time::SystemTime::now()
-        .duration_since(*modified_time)
+    let file_age = SystemTime::now()
+        .duration_since(modified_time)
         .map(|d| d.as_secs_f32())
         .unwrap_or(STALE_FILE_AGE_SECS);
-    let recently_modified = file_age < RECENTLY_MODIFIED_THRESHOLD_SECS;
 
     // Read last N lines efficiently
     let lines = read_last_lines(jsonl_path, JSONL_LINES_TO_SCAN)?;
+    let (usage, model) = jsonl_usage(jsonl_path);
+    let pending_todos = pending_todos(&lines);
 
     let mut session_id = None;
-    let mut last_role = None;
-    let mut has_tool_use = false;
-    let mut has_tool_result = false;
+    let mut turn = LastTurn::default();
     let mut last_message = None;
-    let mut is_local_command = false;
-    let mut is_interrupted = false;
+    let mut has_error = false;
 
     for line in lines.iter().rev() {
         if let Ok(msg) = serde_json::from_str::<JsonlMessage>(line) {
@@ -374,12 +951,20 @@ fn parse_project_session(
 
                     if has_content {
                         // Set status info from the most recent message with content
-                        if last_role.is_none() {
-                            last_role = content.role.clone();
-                            has_tool_use = check_content_type(c, "tool_use");
-                            has_tool_result = check_content_type(c, "tool_result");
-                            is_local_command = check_local_command(c);
-                            is_interrupted = check_interrupted(c);
+                        if turn.role.is_none() {
+                            turn = LastTurn {
+                                role: content.role.clone(),
+                                has_tool_use: check_content_type(c, "tool_use"),
+                                has_tool_result: check_content_type(c, "tool_result"),
+                                is_local_command: check_local_command(c),
+                                is_interrupted: check_interrupted(c),
+                                is_thinking: check_thinking_only(c),
+                            };
+                            has_error = check_tool_error(c);
+                            // Thinking text is redacted; show that it's happening instead
+                            if turn.is_thinking && last_message.is_none() {
+                                last_message = Some(THINKING_MARKER.to_string());
+                            }
                         }
 
                         // Keep looking for text until we find some
@@ -391,7 +976,7 @@ fn parse_project_session(
             }
 
             // Stop when we have all the info we need
-            if session_id.is_some() && last_role.is_some() && last_message.is_some() {
+            if session_id.is_some() && turn.role.is_some() && last_message.is_some() {
                 break;
             }
         }
@@ -400,55 +985,232 @@ fn parse_project_session(
     let session_id = session_id?;
 
     // Determine status
-    let status = determine_status(
-        last_role.as_deref(),
-        has_tool_use,
-        has_tool_result,
-        is_local_command,
-        is_interrupted,
-        recently_modified,
-        cpu_usage,
-    );
+    let status = determine_status(&turn, file_age, cpu_usage, options);
 
-    // Extract project name
-    let project_name = project_path
-        .split('/')
-        .filter(|s| !s.is_empty())
-        .last()
-        .unwrap_or("Unknown")
-        .to_string();
+    let meta = project_meta(project_path);
 
-    // Truncate message
-    let last_message = last_message.map(|m| {
-        if m.chars().count() > MESSAGE_TRUNCATE_LEN {
-            format!("{}...", m.chars().take(MESSAGE_TRUNCATE_LEN).collect::<String>())
-        } else {
-            m
-        }
-    });
+    let last_message = last_message.map(truncate_message);
 
     let tmux_target = tmux_location.as_ref().map(|l| l.to_string());
 
+    // Assistant asked for a tool and nothing is happening: likely a permission prompt
+    let pending_approval = turn.role.as_deref() == Some("assistant")
+        && turn.has_tool_use
+        && status == SessionStatus::Waiting;
+
     Some(Session {
         id: session_id,
-        project_name,
+        project_name: meta.project_name,
         project_path: project_path.to_string(),
         status,
         last_message,
         tmux_location,
         tmux_target,
+        tmux_stale: false,
         cpu_usage,
+        memory_bytes: 0,
         last_activity_secs: file_age as u64,
         pid: Some(pid),
         is_running: true,
-        first_prompt: None,
+        first_prompt: read_first_prompt(jsonl_path),
         message_count: None,
         created_at: None,
-        jsonl_path: None,
+        jsonl_path: Some(jsonl_path.to_string_lossy().to_string()),
+        pending_approval,
+        has_error,
+        needs_attention: false,
+        usage,
+        model,
+        pending_todos,
+        git_branch: meta.git_branch,
+        is_sidechain: false,
+        just_ended: false,
+        projects_root: jsonl_path.parent()
+            .and_then(|d| d.parent())
+            .map(|r| r.to_string_lossy().to_string()),
+        project_missing: false,
     })
 }
 
-/// Read the last N lines from a file efficiently
+/// Display name and git branch for `project_path`, recomputed only when the
+/// cached entry is older than the TTL
+fn project_meta(project_path: &str) -> ProjectMeta {
+    let mut cache_guard = PROJECT_META_CACHE.lock().unwrap();
+    let cache = cache_guard.get_or_insert_with(HashMap::new);
+
+    if let Some(meta) = cache.get(project_path) {
+        if meta.fetched_at.elapsed() < PROJECT_META_CACHE_TTL {
+            return meta.clone();
+        }
+    }
+
+    let meta = ProjectMeta {
+        fetched_at: Instant::now(),
+        project_name: project_name(project_path),
+        git_branch: git_branch(project_path),
+    };
+    cache.insert(project_path.to_string(), meta.clone());
+    meta
+}
+
+/// Last non-empty path component, used as the card title
+fn project_name(project_path: &str) -> String {
+    project_path
+        .split('/')
+        .rfind(|s| !s.is_empty())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Current branch of the repo containing `project_path`.
+/// Paths outside a git repo skip the git call entirely.
+fn git_branch(project_path: &str) -> Option<String> {
+    let in_repo = Path::new(project_path).ancestors().any(|p| p.join(".git").exists());
+    if !in_repo {
+        return None;
+    }
+    Command::new("git")
+        .args(["-C", project_path, "rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|b| !b.is_empty())
+}
+
+/// `git_common_dir` as a string, recomputed only when the cached entry is
+/// older than the TTL
+pub fn repo_dir(project_path: &str) -> Option<String> {
+    let mut cache_guard = REPO_DIR_CACHE.lock().unwrap();
+    let cache = cache_guard.get_or_insert_with(HashMap::new);
+
+    if let Some(entry) = cache.get(project_path) {
+        if entry.fetched_at.elapsed() < PROJECT_META_CACHE_TTL {
+            return entry.dir.clone();
+        }
+    }
+
+    let dir = git_common_dir(project_path).map(|d| d.to_string_lossy().to_string());
+    cache.insert(project_path.to_string(), RepoDir { fetched_at: Instant::now(), dir: dir.clone() });
+    dir
+}
+
+/// Shared git directory of the repo containing `project_path`, the same for
+/// every worktree of a repo (`git rev-parse --git-common-dir`, made absolute)
+fn git_common_dir(project_path: &str) -> Option<PathBuf> {
+    let in_repo = Path::new(project_path).ancestors().any(|p| p.join(".git").exists());
+    if !in_repo {
+        return None;
+    }
+    let output = Command::new("git")
+        .args(["-C", project_path, "rev-parse", "--git-common-dir"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if dir.is_empty() {
+        return None;
+    }
+    // Relative to project_path in the main worktree (e.g. `.git`, `../.git`)
+    Path::new(project_path).join(dir).canonicalize().ok()
+}
+
+/// Token usage over a whole JSONL and the last model it names. Only lines
+/// appended since the previous call are read; a half-written last line
+/// waits for the next one.
+fn jsonl_usage(path: &Path) -> (Usage, Option<String>) {
+    let mut cache = USAGE_TALLIES.lock().unwrap();
+    let tally = cache.get_or_insert_with(HashMap::new).entry(path.to_path_buf()).or_default();
+
+    if let Ok(mut file) = File::open(path) {
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        // Shorter than what was read: rewritten, so start over
+        if len < tally.offset {
+            *tally = UsageTally::default();
+        }
+        if len > tally.offset && file.seek(SeekFrom::Start(tally.offset)).is_ok() {
+            let mut reader = BufReader::new(file.take(len - tally.offset));
+            let mut line = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                if line.last() != Some(&b'\n') {
+                    break;
+                }
+                tally.offset += line.len() as u64;
+                tally.add_line(&line);
+                line.clear();
+            }
+        }
+    }
+    (tally.usage(), tally.model.clone())
+}
+
+/// Forget token tallies of files no longer listed
+fn retain_usage_tallies(sessions: &[Session]) {
+    let listed: HashSet<&Path> = sessions.iter()
+        .filter_map(|s| s.jsonl_path.as_deref().map(Path::new))
+        .collect();
+    if let Some(tallies) = USAGE_TALLIES.lock().unwrap().as_mut() {
+        tallies.retain(|path, _| listed.contains(path.as_path()));
+    }
+}
+
+/// Count the todos not yet completed in the most recent TodoWrite call
+fn pending_todos(lines: &[String]) -> Option<u32> {
+    lines.iter().rev()
+        .filter_map(|line| serde_json::from_str::<JsonlMessage>(line).ok())
+        .filter_map(|msg| msg.message?.content)
+        .find_map(|content| {
+            let todos = content.as_array()?.iter().rev()
+                .find(|block| {
+                    block.get("type").and_then(|t| t.as_str()) == Some("tool_use")
+                        && block.get("name").and_then(|n| n.as_str()) == Some("TodoWrite")
+                })?
+                .get("input")?
+                .get("todos")?
+                .as_array()?;
+            let pending = todos.iter()
+                .filter(|todo| todo.get("status").and_then(|s| s.as_str()) != Some("completed"))
+                .count();
+            Some(pending as u32)
+        })
+}
+
+/// `read_last_message`, reusing the previous result while the file is unchanged
+fn cached_last_message(path: &PathBuf) -> Option<String> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    if let Some(cached) = LAST_MESSAGE_CACHE.lock().unwrap().as_ref().and_then(|c| c.get(path)) {
+        if cached.modified == modified {
+            return cached.message.clone();
+        }
+    }
+    let message = read_last_message(path);
+    LAST_MESSAGE_CACHE.lock().unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(path.clone(), CachedMessage { modified, message: message.clone() });
+    message
+}
+
+/// Find the most recent text message in a JSONL file (cheap tail parse)
+fn read_last_message(path: &PathBuf) -> Option<String> {
+    let lines = read_last_lines(path, JSONL_LINES_TO_SCAN)?;
+    lines.iter().rev()
+        .filter_map(|line| serde_json::from_str::<JsonlMessage>(line).ok())
+        .find_map(|msg| msg.message?.content.as_ref().and_then(extract_text))
+        .map(truncate_message)
+}
+
+/// Truncate a message preview to MESSAGE_MAX_CHARS chars
+fn truncate_message(m: String) -> String {
+    if m.chars().count() > MESSAGE_MAX_CHARS {
+        format!("{}…", m.chars().take(MESSAGE_MAX_CHARS).collect::<String>())
+    } else {
+        m
+    }
+}
+
+/// Read the last N lines from a file efficiently.
+/// Lines are split on raw bytes before decoding, so a multi-byte character
+/// straddling a chunk boundary stays intact; a trailing `\r` is stripped.
 fn read_last_lines(path: &PathBuf, n: usize) -> Option<Vec<String>> {
     let file = File::open(path).ok()?;
     let metadata = file.metadata().ok()?;
@@ -460,8 +1222,9 @@ fn read_last_lines(path: &PathBuf, n: usize) -> Option<Vec<String>> {
 
     // For small files, just read everything
     if file_size < 64 * 1024 {
-        let reader = BufReader::new(file);
-        let lines: Vec<String> = reader.lines().flatten().collect();
+        let mut bytes = Vec::new();
+        BufReader::new(file).read_to_end(&mut bytes).ok()?;
+        let lines: Vec<String> = split_lines(&bytes).map(decode_line).collect();
         let start = lines.len().saturating_sub(n);
         return Some(lines[start..].to_vec());
     }
@@ -471,7 +1234,7 @@ fn read_last_lines(path: &PathBuf, n: usize) -> Option<Vec<String>> {
     let chunk_size = 32 * 1024u64; // 32KB chunks
     let mut lines = Vec::new();
     let mut pos = file_size;
-    let mut remainder = String::new();
+    let mut remainder: Vec<u8> = Vec::new();
 
     while lines.len() < n && pos > 0 {
         let read_size = chunk_size.min(pos);
@@ -479,32 +1242,37 @@ fn read_last_lines(path: &PathBuf, n: usize) -> Option<Vec<String>> {
 
         file.seek(SeekFrom::Start(pos)).ok()?;
         let mut buffer = vec![0u8; read_size as usize];
-        std::io::Read::read_exact(&mut file, &mut buffer).ok()?;
-
-        let chunk = String::from_utf8_lossy(&buffer);
-        let combined = format!("{}{}", chunk, remainder);
-
-        let mut chunk_lines: Vec<&str> = combined.lines().collect();
+        file.read_exact(&mut buffer).ok()?;
+        buffer.extend_from_slice(&remainder);
 
         // The first line might be partial (unless we're at the start of the file)
-        if pos > 0 && !chunk_lines.is_empty() {
-            remainder = chunk_lines.remove(0).to_string();
+        let complete_from = if pos > 0 {
+            match buffer.iter().position(|&b| b == b'\n') {
+                Some(i) => i + 1,
+                None => {
+                    remainder = buffer;
+                    continue;
+                }
+            }
         } else {
-            remainder.clear();
-        }
+            0
+        };
 
         // Add lines in reverse order (we're reading backwards)
-        for line in chunk_lines.into_iter().rev() {
-            lines.push(line.to_string());
+        for line in split_lines(&buffer[complete_from..]).rev() {
+            lines.push(decode_line(line));
             if lines.len() >= n {
                 break;
             }
         }
+
+        buffer.truncate(complete_from.saturating_sub(1));
+        remainder = buffer;
     }
 
     // Include any remaining partial line from the start
     if !remainder.is_empty() && lines.len() < n {
-        lines.push(remainder);
+        lines.push(decode_line(&remainder));
     }
 
     // Reverse to get chronological order
@@ -512,23 +1280,61 @@ fn read_last_lines(path: &PathBuf, n: usize) -> Option<Vec<String>> {
     Some(lines)
 }
 
-const CPU_ACTIVE_THRESHOLD: f32 = 10.0;
+/// Split raw bytes on `\n`, dropping the empty piece after a final newline
+fn split_lines(bytes: &[u8]) -> impl DoubleEndedIterator<Item = &[u8]> {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.split(|&b| b == b'\n').filter(move |_| !bytes.is_empty())
+}
 
-fn determine_status(
-    role: Option<&str>,
+/// Decode one line, tolerating invalid UTF-8 and CRLF endings
+fn decode_line(line: &[u8]) -> String {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+/// Config settings that statuses are derived with
+#[derive(Debug, Clone, Copy)]
+pub struct StatusOptions {
+    /// CPU % above which a process counts as active
+    pub cpu_threshold: f32,
+}
+
+impl StatusOptions {
+    pub fn new(config: &Config) -> Self {
+        Self { cpu_threshold: config.cpu_threshold }
+    }
+}
+
+/// What the most recent message with content says about the turn
+#[derive(Debug, Default)]
+struct LastTurn {
+    role: Option<String>,
     has_tool_use: bool,
     has_tool_result: bool,
     is_local_command: bool,
     is_interrupted: bool,
-    recently_modified: bool,
+    is_thinking: bool,
+}
+
+fn determine_status(
+    turn: &LastTurn,
+    file_age: f32,
     cpu_usage: f32,
+    options: &StatusOptions,
 ) -> SessionStatus {
     // High CPU means Claude is actively working, even if file wasn't just modified
-    let is_active = recently_modified || cpu_usage > CPU_ACTIVE_THRESHOLD;
-
-    match role {
+    let is_active = file_age < RECENTLY_MODIFIED_THRESHOLD_SECS || cpu_usage > options.cpu_threshold;
+    // Thinking can run quietly for a while, but not indefinitely
+    let may_still_be_thinking = file_age < THINKING_GRACE_SECS;
+
+    match turn.role.as_deref() {
+        // A thinking block is always followed by text or a tool call, so the
+        // model is mid-turn unless it has been quiet too long (e.g. killed mid-turn)
+        Some("assistant") if turn.is_thinking && (is_active || may_still_be_thinking) => {
+            SessionStatus::Thinking
+        }
         Some("assistant") => {
-            if has_tool_use {
+            if turn.has_tool_use {
                 if is_active {
                     SessionStatus::Processing
                 } else {
@@ -542,9 +1348,9 @@ fn determine_status(
         }
         Some("user") => {
             // Interrupted requests and local commands mean session is waiting
-            if is_local_command || is_interrupted {
+            if turn.is_local_command || turn.is_interrupted {
                 SessionStatus::Waiting
-            } else if has_tool_result {
+            } else if turn.has_tool_result {
                 if is_active {
                     SessionStatus::Thinking
                 } else {
@@ -566,6 +1372,19 @@ fn determine_status(
     }
 }
 
+/// Check if content is only (possibly redacted) thinking blocks
+fn check_thinking_only(content: &serde_json::Value) -> bool {
+    match content {
+        serde_json::Value::Array(arr) if !arr.is_empty() => arr.iter().all(|item| {
+            matches!(
+                item.get("type").and_then(|t| t.as_str()),
+                Some("thinking") | Some("redacted_thinking")
+            )
+        }),
+        _ => false,
+    }
+}
+
 /// Check if content array contains a specific type (tool_use, tool_result, etc.)
 fn check_content_type(content: &serde_json::Value, type_name: &str) -> bool {
     if let serde_json::Value::Array(arr) = content {
@@ -580,6 +1399,18 @@ fn check_content_type(content: &serde_json::Value, type_name: &str) -> bool {
     }
 }
 
+/// Check if content array contains a tool_result flagged as an error
+fn check_tool_error(content: &serde_json::Value) -> bool {
+    if let serde_json::Value::Array(arr) = content {
+        arr.iter().any(|item| {
+            item.get("type").and_then(|t| t.as_str()) == Some("tool_result")
+                && item.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false)
+        })
+    } else {
+        false
+    }
+}
+
 /// Check if message indicates an interrupted request (user pressed Escape)
 fn check_interrupted(content: &serde_json::Value) -> bool {
     extract_text(content)
@@ -637,61 +1468,378 @@ fn convert_path_to_dir_name(path: &str) -> String {
     result
 }
 
-#[allow(dead_code)]
-/// Convert directory name back to path
+/// Convert directory name back to path. The encoding is lossy (`/` and `-`
+/// both become `-`, `/.` becomes `--`), so the filesystem decides: the first
+/// reading whose directories all exist wins. Paths that no longer exist fall
+/// back to a guess that treats dashes after a `Projects`/`Development` marker
+/// as part of the project name.
 fn convert_dir_name_to_path(dir_name: &str) -> String {
     let name = dir_name.strip_prefix('-').unwrap_or(dir_name);
-    let parts: Vec<&str> = name.split('-').collect();
+    let units = dir_name_units(name);
+    existing_path(Path::new("/"), &units)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| guess_dir_name_path(name))
+}
+
+/// Split an encoded name on `-`, folding the empty piece a `--` leaves into
+/// a leading `.` on the next unit (hidden directory)
+fn dir_name_units(name: &str) -> Vec<String> {
+    let mut units = Vec::new();
+    let mut hidden = false;
+    for part in name.split('-') {
+        if part.is_empty() {
+            hidden = true;
+        } else {
+            units.push(if hidden { format!(".{}", part) } else { part.to_string() });
+            hidden = false;
+        }
+    }
+    units
+}
 
-    if parts.is_empty() {
-        return String::new();
+/// Depth-first search for an existing directory under `base` spelled by
+/// `units`, each path segment joining one or more units with `-`
+fn existing_path(base: &Path, units: &[String]) -> Option<PathBuf> {
+    if units.is_empty() {
+        return Some(base.to_path_buf());
     }
+    // Prefer the longest segment; a hidden unit always starts a new segment
+    let max_len = units.iter().skip(1).position(|u| u.starts_with('.')).map_or(units.len(), |i| i + 1);
+    (1..=max_len).rev().find_map(|len| {
+        let candidate = base.join(units[..len].join("-"));
+        if candidate.is_dir() {
+            existing_path(&candidate, &units[len..])
+        } else {
+            None
+        }
+    })
+}
+
+/// Best-effort path for a directory name that doesn't exist on disk
+fn guess_dir_name_path(name: &str) -> String {
+    let units = dir_name_units(name);
 
     // Find "Projects" or similar markers
-    let projects_idx = parts.iter().position(|&p| p == "Projects" || p == "Development");
-
-    if let Some(idx) = projects_idx {
-        let path_parts = &parts[..=idx];
-        let project_parts = &parts[idx + 1..];
-
-        let mut path = String::from("/");
-        path.push_str(&path_parts.join("/"));
-
-        if !project_parts.is_empty() {
-            path.push('/');
-            // Handle hidden folders (double dash)
-            let mut in_hidden = false;
-            let mut segments: Vec<String> = Vec::new();
-            let mut current = String::new();
-
-            for part in project_parts {
-                if part.is_empty() {
-                    if !current.is_empty() {
-                        segments.push(current);
-                        current = String::new();
-                    }
-                    in_hidden = true;
-                } else if in_hidden {
-                    if current.is_empty() {
-                        current = format!(".{}", part);
-                    } else {
-                        segments.push(current);
-                        current = part.to_string();
-                    }
-                } else if current.is_empty() {
-                    current = part.to_string();
-                } else {
-                    current.push('-');
-                    current.push_str(part);
-                }
-            }
-            if !current.is_empty() {
-                segments.push(current);
+    let Some(idx) = units.iter().position(|u| u == "Projects" || u == "Development") else {
+        return format!("/{}", units.join("/"));
+    };
+
+    let mut segments: Vec<String> = units[..=idx].to_vec();
+    let mut joinable = false;
+    for unit in &units[idx + 1..] {
+        // Hidden folders and whatever follows them are separate segments;
+        // otherwise dashes are assumed to belong to the project name
+        let hidden = unit.starts_with('.');
+        match segments.last_mut() {
+            Some(last) if joinable && !hidden => {
+                last.push('-');
+                last.push_str(unit);
             }
-            path.push_str(&segments.join("/"));
+            _ => segments.push(unit.clone()),
         }
+        joinable = !hidden;
+    }
+    format!("/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Status of a session whose last message is an assistant tool_use
+    fn tool_use_status(recently_modified: bool, cpu_usage: f32, threshold: f32) -> SessionStatus {
+        let turn = LastTurn { role: Some("assistant".to_string()), has_tool_use: true, ..Default::default() };
+        let file_age = if recently_modified { 0.0 } else { STALE_FILE_AGE_SECS };
+        determine_status(&turn, file_age, cpu_usage, &options(threshold))
+    }
+
+    fn turn(role: &str) -> LastTurn {
+        LastTurn { role: Some(role.to_string()), ..Default::default() }
+    }
+
+    fn options(cpu_threshold: f32) -> StatusOptions {
+        StatusOptions { cpu_threshold }
+    }
+
+    #[test]
+    fn tool_use_with_high_cpu_is_processing() {
+        assert_eq!(tool_use_status(false, 55.0, 10.0), SessionStatus::Processing);
+    }
+
+    #[test]
+    fn tool_use_with_low_cpu_is_waiting() {
+        assert_eq!(tool_use_status(false, 2.0, 10.0), SessionStatus::Waiting);
+    }
+
+    #[test]
+    fn tool_use_recently_modified_is_processing_regardless_of_cpu() {
+        assert_eq!(tool_use_status(true, 0.0, 10.0), SessionStatus::Processing);
+    }
+
+    #[test]
+    fn tool_use_respects_configured_threshold() {
+        assert_eq!(tool_use_status(false, 15.0, 20.0), SessionStatus::Waiting);
+        assert_eq!(tool_use_status(false, 25.0, 20.0), SessionStatus::Processing);
+    }
+
+    #[test]
+    fn cpu_at_threshold_is_not_active() {
+        assert_eq!(tool_use_status(false, 10.0, 10.0), SessionStatus::Waiting);
+    }
+
+    #[test]
+    fn tool_result_with_high_cpu_is_thinking() {
+        let turn = LastTurn { has_tool_result: true, ..turn("user") };
+        let status = determine_status(&turn, STALE_FILE_AGE_SECS, 40.0, &options(10.0));
+        assert_eq!(status, SessionStatus::Thinking);
+    }
+
+    #[test]
+    fn interrupted_user_message_ignores_cpu() {
+        let turn = LastTurn { is_interrupted: true, ..turn("user") };
+        let status = determine_status(&turn, STALE_FILE_AGE_SECS, 90.0, &options(10.0));
+        assert_eq!(status, SessionStatus::Waiting);
+    }
+
+    #[test]
+    fn thinking_only_assistant_message_is_thinking_until_the_grace_window_ends() {
+        let content = serde_json::json!([{"type": "thinking", "thinking": "", "signature": "x"}]);
+        assert!(check_thinking_only(&content));
+        let turn = LastTurn { is_thinking: true, ..turn("assistant") };
+        // Quiet, but within the grace window
+        assert_eq!(determine_status(&turn, 60.0, 0.0, &options(10.0)), SessionStatus::Thinking);
+        // Quiet past the window: the turn was abandoned
+        assert_eq!(determine_status(&turn, 600.0, 0.0, &options(10.0)), SessionStatus::Waiting);
+        // Still busy past the window
+        assert_eq!(determine_status(&turn, 600.0, 50.0, &options(10.0)), SessionStatus::Thinking);
+
+        let mixed = serde_json::json!([{"type": "thinking", "thinking": "hm"}, {"type": "text", "text": "done"}]);
+        assert!(!check_thinking_only(&mixed));
+    }
+
+    /// Write `contents` to a fresh file under the temp dir
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("claude-watch-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
         path
-    } else {
-        format!("/{}", name.replace('-', "/"))
+    }
+
+    #[test]
+    fn read_last_lines_keeps_multibyte_char_across_chunk_boundary() {
+        // The last 32KB chunk starts two bytes into the emoji
+        let mut contents = "filler line\r\n".repeat(5000).into_bytes();
+        contents.extend_from_slice("😀".as_bytes());
+        contents.extend_from_slice("b".repeat(32764).as_bytes());
+        contents.extend_from_slice(b"\r\n");
+        let path = temp_file("utf8-boundary.jsonl", &contents);
+
+        let lines = read_last_lines(&path, 2).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(lines[0], "filler line");
+        assert_eq!(lines[1], format!("😀{}", "b".repeat(32764)));
+    }
+
+    #[test]
+    fn read_last_lines_strips_crlf_in_small_files() {
+        let path = temp_file("crlf.jsonl", b"one\r\ntwo\r\nthree\r\n");
+
+        let lines = read_last_lines(&path, 2).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(lines, ["two", "three"]);
+    }
+
+    #[test]
+    fn jsonl_usage_counts_split_messages_once_and_reads_appended_lines() {
+        let line = |id: &str, input: u64, output: u64| format!(
+            r#"{{"message":{{"id":"{}","model":"claude-sonnet-4","usage":{{"input_tokens":{},"output_tokens":{},"cache_read_input_tokens":100}}}}}}"#,
+            id, input, output,
+        );
+        let contents = format!("{}\n{}\n{}\n", line("a", 5, 1), line("a", 5, 9), line("b", 2, 3));
+        let path = temp_file("usage.jsonl", contents.as_bytes());
+
+        let (usage, model) = jsonl_usage(&path);
+        assert_eq!(model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!((usage.input_tokens, usage.output_tokens, usage.cache_read_input_tokens), (7, 12, 200));
+
+        // A half-written line is left for the next read
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        let next = line("c", 1, 1);
+        std::io::Write::write_all(&mut file, &next.as_bytes()[..10]).unwrap();
+        assert_eq!(jsonl_usage(&path).0.input_tokens, 7);
+        std::io::Write::write_all(&mut file, format!("{}\n", &next[10..]).as_bytes()).unwrap();
+        assert_eq!(jsonl_usage(&path).0.input_tokens, 8);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn running_session_hides_its_index_entry() {
+        let historical = |id: &str| Session {
+            id: id.to_string(),
+            jsonl_path: Some(format!("/p/-p/{}.jsonl", id)),
+            ..Default::default()
+        };
+        // Ids agree, ids differ in case/whitespace, and the running side
+        // read no id at all but has the same JSONL file
+        let running = vec![
+            Session { id: "abc".into(), jsonl_path: Some("/p/-p/abc.jsonl".into()), is_running: true, ..Default::default() },
+            Session { id: " DEF".into(), jsonl_path: None, is_running: true, ..Default::default() },
+            Session { id: String::new(), jsonl_path: Some("/p/-p/ghi.jsonl".into()), is_running: true, ..Default::default() },
+        ];
+        let mut rest = vec![historical("abc"), historical("def"), historical("ghi"), historical("other")];
+
+        remove_running_duplicates(&running, &mut rest);
+
+        let ids: Vec<&str> = rest.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["other"]);
+    }
+
+    #[test]
+    fn custom_prefixes_exclude_other_tools_jsonl() {
+        let prefixes = vec!["agent-".to_string(), "backup-".to_string()];
+        assert!(is_jsonl_without_prefix(Path::new("/p/-p/abc.jsonl"), &prefixes));
+        assert!(!is_jsonl_without_prefix(Path::new("/p/-p/agent-1.jsonl"), &prefixes));
+        assert!(!is_jsonl_without_prefix(Path::new("/p/-p/backup-abc.jsonl"), &prefixes));
+        assert!(!is_jsonl_without_prefix(Path::new("/p/-p/abc.json"), &prefixes));
+        // An empty list keeps sub-agent files too
+        assert!(is_jsonl_without_prefix(Path::new("/p/-p/agent-1.jsonl"), &[]));
+    }
+
+    #[test]
+    fn pending_todos_counts_unfinished_items_of_latest_todowrite() {
+        let todo_write = |statuses: &[&str]| {
+            let todos: Vec<_> = statuses.iter()
+                .map(|s| serde_json::json!({"content": "task", "status": s}))
+                .collect();
+            serde_json::json!({"message": {"role": "assistant", "content": [
+                {"type": "tool_use", "name": "TodoWrite", "input": {"todos": todos}}
+            ]}}).to_string()
+        };
+        let text = r#"{"message":{"role":"assistant","content":[{"type":"text","text":"hi"}]}}"#.to_string();
+
+        let lines = vec![
+            todo_write(&["pending", "pending", "pending"]),
+            todo_write(&["completed", "in_progress", "pending"]),
+            text.clone(),
+        ];
+        assert_eq!(pending_todos(&lines), Some(2));
+        assert_eq!(pending_todos(&[text]), None);
+    }
+
+    const INDEX_ENTRY: &str = r#"{"sessionId":"abc","fullPath":"/p/abc.jsonl","firstPrompt":"hi","messageCount":3,"created":"2025-01-01T00:00:00Z","modified":"2025-01-01T00:00:00Z","projectPath":"/p"}"#;
+
+    #[test]
+    fn session_index_skips_malformed_entries() {
+        let content = format!(r#"{{"version":1,"entries":[{},{{"sessionId":42}}]}}"#, INDEX_ENTRY);
+        let (entries, skipped) = parse_session_index(&content).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].session_id, "abc");
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn truncated_session_index_is_unparseable() {
+        let content = format!(r#"{{"version":1,"entries":[{},{{"sessionId":"de"#, INDEX_ENTRY);
+        assert!(parse_session_index(&content).is_none());
+    }
+
+    #[test]
+    fn unreadable_index_falls_back_to_jsonl_files() {
+        let dir = std::env::temp_dir().join(format!("claude-watch-{}-index-fallback", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("s1.jsonl"), "{\"cwd\":\"/home/me/proj\",\"type\":\"user\"}\n").unwrap();
+        fs::write(dir.join("agent-x.jsonl"), "{}\n").unwrap();
+
+        let entries = scan_jsonl_entries(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].session_id, "s1");
+        assert_eq!(entries[0].project_path.as_deref(), Some("/home/me/proj"));
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn session_id_from_resume_and_session_id_flags() {
+        assert_eq!(session_id_from_args(&args(&["claude", "--resume", "abc"])).as_deref(), Some("abc"));
+        assert_eq!(session_id_from_args(&args(&["claude", "-r", "abc"])).as_deref(), Some("abc"));
+        assert_eq!(session_id_from_args(&args(&["claude", "--session-id=abc"])).as_deref(), Some("abc"));
+        assert_eq!(session_id_from_args(&args(&["claude", "--resume", "--verbose"])), None);
+        assert_eq!(session_id_from_args(&args(&["claude", "--continue"])), None);
+    }
+
+    /// Two JSONL files in one project dir, `newer` written most recently
+    fn two_files() -> Vec<(PathBuf, SystemTime)> {
+        let now = SystemTime::now();
+        vec![
+            (PathBuf::from("/p/newer.jsonl"), now),
+            (PathBuf::from("/p/older.jsonl"), now - Duration::from_secs(60)),
+        ]
+    }
+
+    fn assigned_ids(known_ids: &[Option<String>], files: &[(PathBuf, SystemTime)]) -> Vec<Option<String>> {
+        assign_jsonl_files(known_ids, files).into_iter()
+            .map(|f| f.and_then(|(p, _)| p.file_stem()).map(|s| s.to_string_lossy().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn known_session_ids_win_over_recency() {
+        let files = two_files();
+        // The higher PID is the one resumed into the older session
+        let known = [Some("older".to_string()), Some("newer".to_string())];
+        assert_eq!(assigned_ids(&known, &files), [Some("older".into()), Some("newer".into())]);
+    }
+
+    #[test]
+    fn unknown_sessions_take_the_unclaimed_files() {
+        let files = two_files();
+        let known = [None, Some("newer".to_string())];
+        assert_eq!(assigned_ids(&known, &files), [Some("older".into()), Some("newer".into())]);
+    }
+
+    #[test]
+    fn unidentified_concurrent_sessions_fall_back_to_recency() {
+        // Known failure mode: with no ids, the higher PID always gets the most
+        // recently written file, even if it was the lower PID that just wrote
+        let files = two_files();
+        assert_eq!(assigned_ids(&[None, None], &files), [Some("newer".into()), Some("older".into())]);
+        // More processes than files leaves the extra one unassigned
+        assert_eq!(assigned_ids(&[None, None, None], &files)[2], None);
+    }
+
+    fn round_trip(path: &str) -> String {
+        convert_dir_name_to_path(&convert_path_to_dir_name(path))
+    }
+
+    #[test]
+    fn dir_name_round_trips_through_existing_dirs() {
+        let base = std::env::temp_dir().join(format!("claude-watch-{}-round-trip", std::process::id()));
+        let paths = [
+            base.join("Development").join("my-app"),
+            base.join("Development").join("my-app").join(".claude").join("worktrees"),
+            base.join("plain").join(".config").join("nvim-lua"),
+            base.join("a-b-c").join("d"),
+        ];
+        for path in &paths {
+            fs::create_dir_all(path).unwrap();
+        }
+        let results: Vec<String> = paths.iter().map(|p| round_trip(&p.to_string_lossy())).collect();
+        fs::remove_dir_all(&base).unwrap();
+
+        for (path, result) in paths.iter().zip(results) {
+            assert_eq!(result, path.to_string_lossy());
+        }
+    }
+
+    #[test]
+    fn missing_dir_names_are_guessed_around_markers() {
+        assert_eq!(round_trip("/nonexistent/Development/my-app"), "/nonexistent/Development/my-app");
+        assert_eq!(round_trip("/nonexistent/Projects/app/.worktrees/fix-bug"), "/nonexistent/Projects/app/.worktrees/fix-bug");
+        assert_eq!(round_trip("/nonexistent/code/.hidden/app"), "/nonexistent/code/.hidden/app");
     }
 }
@@ -0,0 +1,43 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::ViewMode;
+
+/// UI state carried across restarts, stored in ~/.cache/claude-watch/state.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    pub view_mode: ViewMode,
+    /// Id of the session selected on exit
+    pub selected_session_id: Option<String>,
+    /// Ids of sessions pinned with `p`
+    pub pinned_session_ids: Vec<String>,
+}
+
+/// Path to the state file
+pub fn state_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".cache").join("claude-watch").join("state.json"))
+}
+
+/// Load saved state, falling back to defaults if missing or unreadable
+pub fn load() -> State {
+    state_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// Save state; failures are ignored since it's only a convenience
+pub fn save(state: &State) {
+    let path = match state_path() {
+        Some(p) => p,
+        None => return,
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(state) {
+        let _ = fs::write(path, json);
+    }
+}
@@ -0,0 +1,145 @@
+use ratatui::style::{Color, Modifier, Style};
+
+/// Named UI styles, by role rather than by palette name
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Titles and Thinking status
+    pub accent: Style,
+    /// Destructive prompts and needs-attention badge
+    pub alert: Style,
+    /// Processing status
+    pub working: Style,
+    /// Waiting status, key hints, user messages
+    pub info: Style,
+    /// Overlay borders
+    pub highlight: Style,
+    /// Borders, hints, idle status
+    pub subtle: Style,
+    /// Secondary text and historical sessions
+    pub muted: Style,
+    /// Primary text
+    pub text: Style,
+    /// Selected card background
+    pub selection: Style,
+    /// Scrollbar track
+    pub track: Style,
+}
+
+/// Text in `color`
+const fn fg(color: Color) -> Style {
+    Style::new().fg(color)
+}
+
+impl Theme {
+    /// Rosé Pine Moon (matching the tmux theme)
+    pub fn rose_pine_moon() -> Self {
+        Self {
+            accent: fg(Color::Rgb(246, 193, 119)),    // gold #f6c177
+            alert: fg(Color::Rgb(235, 111, 146)),     // love #eb6f92
+            working: fg(Color::Rgb(62, 143, 176)),    // pine #3e8fb0
+            info: fg(Color::Rgb(156, 207, 216)),      // foam #9ccfd8
+            highlight: fg(Color::Rgb(196, 167, 231)), // iris #c4a7e7
+            subtle: fg(Color::Rgb(110, 106, 134)),    // subtle #6e6a86
+            muted: fg(Color::Rgb(144, 140, 170)),     // muted #908caa
+            text: fg(Color::Rgb(224, 222, 244)),      // text #e0def4
+            selection: Style::new().bg(Color::Rgb(57, 53, 82)), // overlay #393552
+            track: fg(Color::Rgb(57, 53, 82)),
+        }
+    }
+
+    /// Terminal palette colors for dark backgrounds
+    pub fn default_dark() -> Self {
+        Self {
+            accent: fg(Color::Yellow),
+            alert: fg(Color::LightRed),
+            working: fg(Color::Blue),
+            info: fg(Color::Cyan),
+            highlight: fg(Color::Magenta),
+            subtle: fg(Color::Indexed(243)),
+            muted: fg(Color::Indexed(248)),
+            text: fg(Color::Reset),
+            selection: Style::new().bg(Color::Indexed(237)),
+            track: fg(Color::Indexed(237)),
+        }
+    }
+
+    /// Terminal palette colors for light backgrounds
+    pub fn default_light() -> Self {
+        Self {
+            accent: fg(Color::Indexed(130)),
+            alert: fg(Color::Indexed(160)),
+            working: fg(Color::Indexed(25)),
+            info: fg(Color::Indexed(30)),
+            highlight: fg(Color::Indexed(91)),
+            subtle: fg(Color::Indexed(245)),
+            muted: fg(Color::Indexed(241)),
+            text: fg(Color::Reset),
+            selection: Style::new().bg(Color::Indexed(253)),
+            track: fg(Color::Indexed(253)),
+        }
+    }
+
+    /// No colors (--no-color or $NO_COLOR): the terminal's own colors, with
+    /// bold for accents, dim for secondary text, and a reversed selection
+    pub fn monochrome() -> Self {
+        let plain = fg(Color::Reset);
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let dim = plain.add_modifier(Modifier::DIM);
+        Self {
+            accent: bold,
+            alert: bold,
+            working: plain,
+            info: plain,
+            highlight: bold,
+            subtle: dim,
+            muted: dim,
+            text: plain,
+            selection: Style::new().add_modifier(Modifier::REVERSED),
+            track: dim,
+        }
+    }
+
+    /// Look up a theme by config name (case and `_`/`-` insensitive)
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('_', "-").as_str() {
+            "rose-pine-moon" => Some(Self::rose_pine_moon()),
+            "dark" | "default-dark" => Some(Self::default_dark()),
+            "light" | "default-light" => Some(Self::default_light()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::rose_pine_moon()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::text::Span;
+    use ratatui::widgets::{Paragraph, Widget};
+
+    #[test]
+    fn monochrome_marks_roles_with_attributes_only() {
+        let theme = Theme::monochrome();
+        let area = Rect::new(0, 0, 8, 1);
+        let mut buf = Buffer::empty(area);
+        // A selected card: the selection fill, then text drawn over it
+        Paragraph::new("        ").style(theme.selection).render(area, &mut buf);
+        Paragraph::new(Span::styled("alert", theme.alert)).render(area, &mut buf);
+        Paragraph::new(Span::styled("  ", theme.muted)).render(Rect::new(5, 0, 2, 1), &mut buf);
+
+        for cell in buf.content.iter() {
+            assert_eq!((cell.fg, cell.bg), (Color::Reset, Color::Reset));
+            assert!(cell.modifier.contains(Modifier::REVERSED));
+        }
+        assert!(buf[(0, 0)].modifier.contains(Modifier::BOLD));
+        assert!(buf[(5, 0)].modifier.contains(Modifier::DIM));
+        assert!(!buf[(7, 0)].modifier.intersects(Modifier::BOLD | Modifier::DIM));
+    }
+}
@@ -1,12 +1,103 @@
 use std::collections::HashMap;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Socket from the config or $CLAUDE_WATCH_TMUX_SOCKET (set once at startup)
+static SOCKET_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Use this tmux socket for every tmux command instead of the one in $TMUX
+pub fn set_socket(path: &str) {
+    if !path.is_empty() {
+        let _ = SOCKET_OVERRIDE.set(path.to_string());
+    }
+}
+
+/// How long a pane map may be reused (set once at startup; unset = every call)
+static PANE_MAP_TTL: OnceLock<Duration> = OnceLock::new();
+
+// Last pane map and when it was fetched
+static PANE_MAP_CACHE: Mutex<Option<(Instant, HashMap<u32, TmuxLocation>)>> = Mutex::new(None);
+
+/// Reuse the pane map for up to `ttl` instead of listing panes every refresh
+pub fn set_pane_map_ttl(ttl: Duration) {
+    let _ = PANE_MAP_TTL.set(ttl);
+}
+
+/// Forget the cached pane map so the next refresh lists panes again
+pub fn invalidate_pane_map() {
+    *PANE_MAP_CACHE.lock().unwrap() = None;
+}
+
+/// Socket path tmux commands should use: the override, else the first
+/// comma-separated field of $TMUX (the server we're running inside)
+fn socket_path() -> Option<String> {
+    if let Some(path) = SOCKET_OVERRIDE.get() {
+        return Some(path.clone());
+    }
+    std::env::var("TMUX").ok()?
+        .split(',')
+        .next()
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+}
+
+/// A `tmux` command bound to the right server socket
+fn tmux_command() -> Command {
+    let mut cmd = Command::new("tmux");
+    if let Some(path) = socket_path() {
+        cmd.args(["-S", &path]);
+    }
+    cmd
+}
+
+// Last failure of a tmux command nobody was waiting on (pane listing, kill-window)
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Take the last background tmux failure, if any, for showing once
+pub fn take_error() -> Option<String> {
+    LAST_ERROR.lock().unwrap().take()
+}
+
+fn record_error(error: String) {
+    *LAST_ERROR.lock().unwrap() = Some(error);
+}
+
+/// Run a tmux command, returning its stdout, or on failure the first line
+/// of its stderr (e.g. "can't find window: 3")
+fn run(args: &[&str]) -> Result<String, String> {
+    let output = tmux_command()
+        .args(args)
+        .output()
+        .map_err(|e| format!("can't run tmux: {}", e))?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(match stderr.lines().map(str::trim).find(|l| !l.is_empty()) {
+        Some(line) => line.to_string(),
+        None => format!("tmux {} exited with {}", args[0], output.status),
+    })
+}
 
 #[derive(Debug, Clone)]
 pub struct TmuxLocation {
     pub session: String,
     pub window_index: u32,
-    #[allow(dead_code)]
     pub window_name: String,
+    /// tmux pane id (e.g. `%3`), for landing on the right pane of a split window
+    pub pane_id: Option<String>,
+}
+
+impl TmuxLocation {
+    /// Window name for display, or the index when the name is empty or numeric
+    pub fn window_label(&self) -> String {
+        if self.window_name.is_empty() || self.window_name.chars().all(|c| c.is_ascii_digit()) {
+            self.window_index.to_string()
+        } else {
+            self.window_name.clone()
+        }
+    }
 }
 
 impl std::fmt::Display for TmuxLocation {
@@ -15,29 +106,46 @@ impl std::fmt::Display for TmuxLocation {
     }
 }
 
-/// Get mapping of shell PID -> tmux location
+/// Get mapping of shell PID -> tmux location, cached for the configured TTL
 pub fn get_pane_map() -> HashMap<u32, TmuxLocation> {
+    let ttl = PANE_MAP_TTL.get().copied().unwrap_or_default();
+    let mut cache = PANE_MAP_CACHE.lock().unwrap();
+    if let Some((fetched_at, ref map)) = *cache {
+        if fetched_at.elapsed() < ttl {
+            return map.clone();
+        }
+    }
+
+    let map = list_panes();
+    if !ttl.is_zero() {
+        *cache = Some((Instant::now(), map.clone()));
+    }
+    map
+}
+
+/// One `tmux list-panes -a` call, parsed
+fn list_panes() -> HashMap<u32, TmuxLocation> {
     let mut map = HashMap::new();
 
-    let output = Command::new("tmux")
-        .args(["list-panes", "-a", "-F", "#{pane_pid}:#{session_name}:#{window_index}:#{window_name}"])
-        .output();
-
-    if let Ok(output) = output {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                let parts: Vec<&str> = line.splitn(4, ':').collect();
-                if parts.len() == 4 {
-                    if let Ok(pid) = parts[0].parse::<u32>() {
-                        if let Ok(window_index) = parts[2].parse::<u32>() {
-                            map.insert(pid, TmuxLocation {
-                                session: parts[1].to_string(),
-                                window_index,
-                                window_name: parts[3].to_string(),
-                            });
-                        }
-                    }
+    let stdout = match run(&["list-panes", "-a", "-F", "#{pane_pid}:#{session_name}:#{window_index}:#{pane_id}:#{window_name}"]) {
+        Ok(stdout) => stdout,
+        Err(e) => {
+            record_error(format!("tmux list-panes: {}", e));
+            return map;
+        }
+    };
+    for line in stdout.lines() {
+        // Window name last: it may contain ':'
+        let parts: Vec<&str> = line.splitn(5, ':').collect();
+        if parts.len() == 5 {
+            if let Ok(pid) = parts[0].parse::<u32>() {
+                if let Ok(window_index) = parts[2].parse::<u32>() {
+                    map.insert(pid, TmuxLocation {
+                        session: parts[1].to_string(),
+                        window_index,
+                        window_name: parts[4].to_string(),
+                        pane_id: Some(parts[3].to_string()),
+                    });
                 }
             }
         }
@@ -46,42 +154,72 @@ pub fn get_pane_map() -> HashMap<u32, TmuxLocation> {
     map
 }
 
-/// Switch to a specific tmux window
-pub fn switch_to_window(location: &TmuxLocation) {
+/// Switch to a specific tmux window and pane, moving the client to the
+/// window's session first when it's attached elsewhere. Fails with tmux's
+/// message when a step does (e.g. the window was closed).
+pub fn switch_to_window(location: &TmuxLocation) -> Result<(), String> {
+    invalidate_pane_map();
+    let current_session = run(&["display-message", "-p", "#{session_name}"])
+        .ok()
+        .map(|s| s.trim().to_string());
+    if current_session.as_deref() != Some(location.session.as_str()) {
+        run(&["switch-client", "-t", &location.session])?;
+    }
+
     let target = format!("{}:{}", location.session, location.window_index);
-    let _ = Command::new("tmux")
-        .args(["select-window", "-t", &target])
-        .status();
+    run(&["select-window", "-t", &target])?;
+
+    if let Some(ref pane_id) = location.pane_id {
+        run(&["select-pane", "-t", pane_id])?;
+    }
+    Ok(())
+}
+
+/// Close a tmux window (and any other panes in it)
+pub fn kill_window(location: &TmuxLocation) {
+    invalidate_pane_map();
+    if let Err(e) = run(&["kill-window", "-t", &location.to_string()]) {
+        record_error(format!("tmux kill-window: {}", e));
+    }
 }
 
 /// Show a brief notification in tmux status bar
 pub fn notify(msg: &str) {
     if std::env::var("TMUX").is_ok() {
-        let _ = Command::new("tmux")
+        let _ = tmux_command()
             .args(["display-message", msg])
             .status();
     }
 }
 
-/// Create a new tmux window with claude --resume command
-pub fn new_window_with_command(window_name: &str, project_path: &str, session_id: &str) {
-    let cmd = format!(
-        "cd '{}' && claude --resume {}",
-        project_path.replace('\'', "'\\''"),
-        session_id
-    );
+/// Type a shell command into the client's current pane and press Enter: the
+/// pane under a popup, or our own pane once we've exited
+pub fn send_to_current_pane(cmd: &str) -> Result<(), String> {
+    invalidate_pane_map();
+    let pane = run(&["display-message", "-p", "#{pane_id}"])?;
+    let pane = pane.trim();
+    run(&["send-keys", "-t", pane, "-l", cmd])?;
+    run(&["send-keys", "-t", pane, "Enter"])?;
+    Ok(())
+}
 
+/// Create a new tmux window running a shell command in the user's shell
+/// (login and interactive, so its PATH setup applies); failures are
+/// recorded for `take_error`
+pub fn new_window_with_command(window_name: &str, cmd: &str) -> bool {
+    invalidate_pane_map();
     // Get current tmux session name (works from popups too)
-    let target = Command::new("tmux")
-        .args(["display-message", "-p", "#{session_name}"])
-        .output()
-        .ok()
-        .and_then(|o| String::from_utf8(o.stdout).ok())
+    let target = run(&["display-message", "-p", "#{session_name}"])
         .map(|s| format!("{}:", s.trim()))
-        .unwrap_or_else(|| ":".to_string());
+        .unwrap_or_else(|_| ":".to_string());
 
-    let _ = Command::new("tmux")
-        .args(["new-window", "-t", &target, "-n", window_name, &cmd])
-        .status();
+    let shell = crate::mux::user_shell();
+    match run(&["new-window", "-t", &target, "-n", window_name, &shell, "-lic", cmd]) {
+        Ok(_) => true,
+        Err(e) => {
+            record_error(format!("tmux new-window: {}", e));
+            false
+        }
+    }
 }
 
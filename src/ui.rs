@@ -1,60 +1,227 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, Paragraph, Padding};
-
-use crate::session::{Session, SessionStatus};
-use crate::log_view::{self, LogMessage};
-
-// Rose Pine Moon colors (matching your tmux theme)
-const GOLD: Color = Color::Rgb(246, 193, 119);      // #f6c177
-#[allow(dead_code)]
-const ROSE: Color = Color::Rgb(235, 111, 146);      // #eb6f92
-const PINE: Color = Color::Rgb(62, 143, 176);       // #3e8fb0
-const FOAM: Color = Color::Rgb(156, 207, 216);      // #9ccfd8
-#[allow(dead_code)]
-const IRIS: Color = Color::Rgb(196, 167, 231);      // #c4a7e7
-const SUBTLE: Color = Color::Rgb(110, 106, 134);    // #6e6a86
-const MUTED: Color = Color::Rgb(144, 140, 170);     // #908caa
-const TEXT: Color = Color::Rgb(224, 222, 244);      // #e0def4
-#[allow(dead_code)]
-const SURFACE: Color = Color::Rgb(42, 39, 63);      // #2a273f
-const OVERLAY: Color = Color::Rgb(57, 53, 82);      // #393552
-
-pub fn draw(frame: &mut Frame, sessions: &[Session], selected: usize, log_messages: &[LogMessage], view_mode: &str) {
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, Paragraph, Padding, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap,
+};
+
+use crate::{App, Focus, Overlay, ViewMode};
+use crate::config::Config;
+use crate::keymap::{Action, Keymap};
+use crate::process::ProcessAncestor;
+use crate::session::{self, Session, SessionStatus};
+use crate::log_view::{self, LogOptions};
+use crate::theme::Theme;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Longest git branch shown on a card before truncating
+const MAX_BRANCH_LEN: usize = 20;
+const MAX_WINDOW_LABEL_LEN: usize = 16;
+/// Status icon frames for Thinking/Processing sessions, one per draw
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+/// Share of the screen height given to the session list when the log is shown
+const LIST_HEIGHT_PERCENT: u16 = 50;
+/// Smallest session list: borders, one card, legend and help bar
+const MIN_LIST_HEIGHT: u16 = 6;
+/// Below this many rows for the log, it's dropped and the list fills the screen
+const MIN_LOG_HEIGHT: u16 = 5;
+/// Narrower than this, nothing useful fits
+const MIN_WIDTH: u16 = 20;
+
+/// Keys of a help row: the keys bound to these actions, every key bound
+/// to one action (the help bar otherwise shows only the first), or fixed
+/// text for keys the keymap doesn't cover
+enum HelpKeys {
+    Bound(&'static [Action]),
+    AllBound(Action),
+    Fixed(&'static str),
+}
+
+use HelpKeys::{AllBound, Bound, Fixed};
+
+/// Keybindings shown by `?`, by category (keep in sync with `App::perform`)
+const HELP_SECTIONS: &[(&str, &[(HelpKeys, &str)])] = &[
+    ("Navigation", &[
+        (Bound(&[Action::Down]), "Move down"),
+        (Bound(&[Action::Up]), "Move up"),
+        (Fixed("1-99"), "Jump to session by number"),
+        (Bound(&[Action::NextAttention]), "Next session that needs you"),
+        (Bound(&[Action::ToggleFocus]), "Focus list / log (j k PgDn /)"),
+        (Bound(&[Action::Filter]), "Filter by project or message"),
+        (Bound(&[Action::Command]), "Command prompt"),
+        (Bound(&[Action::Help]), "This help"),
+        (Bound(&[Action::Quit, Action::Back]), "Quit"),
+    ]),
+    ("Actions", &[
+        (Bound(&[Action::Go]), "Go to session (switch or resume)"),
+        (Bound(&[Action::ResumeHere]), "Resume in the current pane"),
+        (Bound(&[Action::NewSession]), "New session in the project"),
+        (Bound(&[Action::Kill]), "Kill (SIGKILL after 2s if ignored)"),
+        (Bound(&[Action::ForceKill]), "Force kill (SIGKILL after 0.5s if ignored)"),
+        (Bound(&[Action::Delete]), "Delete historical session"),
+        (Bound(&[Action::Pin]), "Pin to the top"),
+        (Bound(&[Action::CopyMessage]), "Copy the session's last message"),
+        (Bound(&[Action::CopyLogMessage]), "Copy latest assistant log message"),
+        (Bound(&[Action::Export]), "Export conversation to Markdown"),
+        (Bound(&[Action::OpenInEditor]), "Open the raw JSONL in $EDITOR"),
+        (Bound(&[Action::Refresh]), "Refresh sessions"),
+        (Bound(&[Action::ReloadLog]), "Re-read the log file"),
+    ]),
+    ("View", &[
+        (Bound(&[Action::ToggleView]), "Running / All sessions"),
+        (Bound(&[Action::CycleSort]), "Cycle sort order"),
+        (Bound(&[Action::BusyOnly]), "Busy sessions only"),
+        (Bound(&[Action::WaitingOnly]), "Waiting sessions only"),
+        (Bound(&[Action::HideIdle]), "Hide idle running sessions"),
+        (Bound(&[Action::GroupByRepo]), "Group by repo (worktrees)"),
+        (Bound(&[Action::ToggleSidechains]), "Show sub-agent sessions"),
+        (Bound(&[Action::ToggleLastMessage]), "First prompt / last message"),
+        (Bound(&[Action::ToggleCreatedTime]), "Created / last active time"),
+        (Bound(&[Action::ToggleCompact]), "One-line cards"),
+        (Bound(&[Action::ToggleAttentionBadge]), "Needs-you badge"),
+        (Bound(&[Action::Pause]), "Pause auto-refresh"),
+        (Bound(&[Action::Detail]), "Session details"),
+        (Bound(&[Action::ProcessTree]), "Process tree"),
+        (Bound(&[Action::Diagnostics]), "Diagnostics (with --debug)"),
+    ]),
+    ("Log", &[
+        (Bound(&[Action::PageDown, Action::PageUp]), "Older / newer (log focused)"),
+        (Bound(&[Action::HalfPageDown, Action::HalfPageUp]), "Half page older / newer"),
+        (Bound(&[Action::LogNewest, Action::LogOldest]), "Newest / oldest"),
+        (Bound(&[Action::ToggleWrap]), "Wrap long lines"),
+        (Bound(&[Action::ScrollLeft, Action::ScrollRight]), "Scroll unwrapped lines"),
+        (Bound(&[Action::CycleRoleFilter]), "All / user / assistant messages"),
+        (Bound(&[Action::ToggleTools]), "Tool call summaries"),
+        (Bound(&[Action::ToggleLogSource]), "Session's log / project's latest"),
+        (Bound(&[Action::SearchLog]), "Search the log"),
+        (Fixed("n / N"), "Next / previous match"),
+    ]),
+];
+
+/// Short help bar entries, labelled with each action's first key
+const HELP_BAR: &[(HelpKeys, &str)] = &[
+    (Bound(&[Action::Filter]), "filter"),
+    (Fixed("1-99"), "jump"),
+    (Bound(&[Action::Down, Action::Up]), "nav"),
+    (AllBound(Action::Go), "go"),
+    (Bound(&[Action::Kill]), "kill"),
+    (Bound(&[Action::Delete]), "del"),
+    (Bound(&[Action::ToggleView]), "view"),
+    (Bound(&[Action::ToggleLastMessage]), "msg"),
+    (Bound(&[Action::BusyOnly]), "busy"),
+    (Bound(&[Action::NextAttention]), "next‼"),
+    (Bound(&[Action::Help]), "help"),
+    (Bound(&[Action::Detail]), "info"),
+    (Bound(&[Action::Quit]), "quit"),
+];
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    draw_main(frame, app);
+
+    if let Some(ref overlay) = app.overlay {
+        match overlay {
+            Overlay::Confirm { message, .. } => render_confirm(frame, theme, message),
+            Overlay::ProcessTree { ancestors, tmux_target } => {
+                render_process_tree(frame, theme, ancestors, tmux_target.as_deref());
+            }
+            Overlay::Detail { session_id } => {
+                if let Some(session) = app.sessions.iter().find(|s| &s.id == session_id) {
+                    render_detail(frame, theme, session, app.cpu_history.get(session_id));
+                }
+            }
+            Overlay::Help => render_help(frame, theme, &app.keymap),
+        }
+    }
+}
+
+fn draw_main(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
     let area = frame.area();
+    let sessions = &app.sessions;
+    let selected = app.selected;
 
-    // Vertical stack: sessions on top, log below
+    if area.height < MIN_LIST_HEIGHT || area.width < MIN_WIDTH {
+        let msg = Paragraph::new("Terminal too small")
+            .style(theme.muted)
+            .alignment(Alignment::Center);
+        let y = area.y + area.height / 2;
+        frame.render_widget(msg, Rect::new(area.x, y, area.width, area.height.min(1)));
+        return;
+    }
+
+    // Vertical stack: sessions on top, log below. The list gets a share of
+    // the height; when that leaves too little for the log, the list fills
+    // the screen alone.
+    // In u32: height * percent overflows u16 above 1310 rows (e.g. --once --size)
+    let list_height = (area.height as u32 * LIST_HEIGHT_PERCENT as u32 / 100) as u16;
+    let list_height = list_height.max(MIN_LIST_HEIGHT);
+    let show_log = area.height >= list_height + MIN_LOG_HEIGHT;
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(18), // ~6 sessions visible (3 lines each)
-            Constraint::Min(5),     // Log takes remaining space
-        ])
+        .constraints(if show_log {
+            [Constraint::Length(list_height), Constraint::Min(0)]
+        } else {
+            [Constraint::Min(0), Constraint::Length(0)]
+        })
         .split(area);
 
     let list_area = main_chunks[0];
     let log_area = main_chunks[1];
 
     // Left pane: session list
-    let title = format!(" Claude ({}) ", view_mode);
-    let block = Block::default()
+    let view_label = app.view_label();
+    let title = if app.total_sessions > sessions.len() {
+        format!(" Claude ({}, showing {} of {}) ", view_label, sessions.len(), app.total_sessions)
+    } else {
+        format!(" Claude ({}) ", view_label)
+    };
+    // The focused pane gets a thick, bright border
+    let list_focused = app.focus == Focus::Sessions;
+    let mut block = Block::default()
         .title(title)
-        .title_style(Style::default().bold().fg(GOLD))
+        .title_style(theme.accent.bold())
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(SUBTLE))
+        .border_type(if list_focused { BorderType::Thick } else { BorderType::Plain })
+        .border_style(if list_focused { theme.highlight } else { theme.subtle })
         .padding(Padding::horizontal(1));
+    if app.paused {
+        block = block.title(Line::styled(" ⏸ PAUSED ", theme.alert.bold()));
+    }
+    block = block.title_bottom(status_summary(theme, sessions, &app.config).centered());
+    if !app.mux.is_multiplexer() {
+        block = block.title(
+            Line::styled(" no multiplexer detected ", theme.muted).right_aligned(),
+        );
+    }
 
     let inner = block.inner(list_area);
     frame.render_widget(block, list_area);
 
     // Right pane: log view
-    log_view::render_log(frame, log_area, log_messages);
-
-    if sessions.is_empty() {
-        let empty_msg = Paragraph::new("No active sessions")
-            .style(Style::default().fg(MUTED))
-            .alignment(Alignment::Center);
-        frame.render_widget(empty_msg, inner);
-        return;
+    let log_options = LogOptions {
+        follow_latest: app.log_follow_latest,
+        wrap: app.log_wrap,
+        role_filter: app.log_role_filter,
+        show_tools: app.log_show_tools,
+        search: &app.log_search,
+        focused: app.focus == Focus::Log,
+    };
+    if show_log {
+        let log_area = if app.show_diagnostics {
+            let lines = diagnostics_lines(app, log_area.width);
+            let height = (lines.len() as u16 + 2).min(log_area.height / 2);
+            let [diag_area, rest] = Layout::vertical([Constraint::Length(height), Constraint::Min(0)])
+                .areas(log_area);
+            render_diagnostics(frame, theme, diag_area, lines);
+            rest
+        } else {
+            log_area
+        };
+        let status = sessions.get(selected)
+            .filter(|s| s.is_running)
+            .map(|s| &s.status);
+        log_view::render_log(frame, theme, log_area, &app.log_messages, &log_options, &app.log_scroll, status);
     }
 
     // Calculate layout: sessions area + legend + help bar
@@ -71,66 +238,395 @@ pub fn draw(frame: &mut Frame, sessions: &[Session], selected: usize, log_messag
     let legend_area = chunks[1];
     let help_area = chunks[2];
 
-    // Compact cards: 2 lines each (project+window, message)
-    let card_height = 2u16;
-    let visible_cards = (sessions_area.height / card_height).max(1) as usize;
+    // Command prompt, or the filter prompt while typing or while a filter
+    // is active, replaces the help bar
+    if let Some(ref input) = app.command_input {
+        render_command_bar(frame, theme, help_area, input);
+    } else if app.log_search_input {
+        render_log_search_bar(frame, theme, help_area, &app.log_search);
+    } else if app.filter_input || !app.filter_query.is_empty() {
+        render_filter_bar(frame, theme, help_area, &app.filter_query, app.filter_input);
+    }
 
-    // Scroll to keep selected visible
-    let scroll_offset = if selected >= visible_cards {
-        selected - visible_cards + 1
-    } else {
-        0
+    if sessions.is_empty() {
+        // An empty list because we couldn't look is an error, not "no sessions"
+        let filtered = !app.filter_query.is_empty() || app.busy_only || app.waiting_only;
+        let (msg, style) = match app.lookup_error {
+            Some(ref e) => (e.to_string(), theme.alert),
+            None => (empty_message(app.view_mode, filtered, &session::projects_dirs()), theme.muted),
+        };
+        let empty_msg = Paragraph::new(msg)
+            .style(style)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(empty_msg, sessions_area);
+        return;
+    }
+
+    // Cards: 2 lines each (project+window, message), or 1 in compact mode.
+    // When the list overflows, the bottom row is kept for a "more" hint.
+    // Grouped by repo, a header row goes above the first card of each group
+    // and above the first card shown, so a scrolled group keeps its name.
+    let card_height = if app.compact_cards { 1u16 } else { 2u16 };
+    let group_breaks: Vec<bool> = (0..sessions.len())
+        .map(|i| app.group_by_repo && i > 0 && app.repo_dir(&sessions[i]) != app.repo_dir(&sessions[i - 1]))
+        .collect();
+    let has_header = |i: usize, first: usize| app.group_by_repo && (i == first || group_breaks[i]);
+    let rows = |first: usize, last: usize| -> u16 {
+        (first..=last).map(|i| card_height + has_header(i, first) as u16).sum()
     };
+    let overflows = rows(0, sessions.len() - 1) > sessions_area.height;
+    let cards_height = if overflows { sessions_area.height.saturating_sub(1) } else { sessions_area.height };
+
+    // Scroll to keep selected visible (at most a screenful of cards back)
+    let mut scroll_offset = (selected + 1).saturating_sub((cards_height / card_height).max(1) as usize);
+    while scroll_offset < selected && rows(scroll_offset, selected) > cards_height {
+        scroll_offset += 1;
+    }
 
     let mut y = sessions_area.y;
+    let mut visible_cards = 0;
     for (i, session) in sessions.iter().enumerate().skip(scroll_offset) {
-        if y + card_height > sessions_area.y + sessions_area.height {
+        let header = has_header(i, scroll_offset);
+        if y + card_height + header as u16 > sessions_area.y + cards_height {
             break;
         }
+        if header {
+            render_group_header(frame, app, session, Rect::new(sessions_area.x, y, sessions_area.width, 1));
+            y += 1;
+        }
 
         let card_area = Rect::new(sessions_area.x, y, sessions_area.width, card_height);
         let is_selected = i == selected;
-        render_session_card(frame, session, card_area, is_selected, i);
+        render_session_card(frame, app, session, card_area, is_selected, i);
         y += card_height;
+        visible_cards += 1;
+    }
+    let visible_cards = visible_cards.max(1);
+    app.visible_cards.set(visible_cards);
+
+    if overflows {
+        // Scrollbar in the block's right padding column
+        let track = Rect::new(sessions_area.right(), sessions_area.y, 1, cards_height);
+        let mut scrollbar_state = ScrollbarState::new(sessions.len())
+            .viewport_content_length(visible_cards)
+            .position(selected);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .symbols(ratatui::symbols::scrollbar::VERTICAL)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(theme.track)
+            .thumb_style(theme.subtle);
+        frame.render_stateful_widget(scrollbar, track, &mut scrollbar_state);
+
+        let hidden_below = sessions.len().saturating_sub(scroll_offset + visible_cards);
+        if hidden_below > 0 {
+            let hint_area = Rect::new(sessions_area.x, sessions_area.y + cards_height, sessions_area.width, 1);
+            let hint = Paragraph::new(format!("▾ {} more", hidden_below))
+                .style(theme.subtle)
+                .alignment(Alignment::Center);
+            frame.render_widget(hint, hint_area);
+        }
     }
 
     // Legend bar (matches tmux tab icons)
     let legend = Paragraph::new(Line::from(vec![
-        Span::styled("↻ ", Style::default().fg(GOLD)),
-        Span::styled("work  ", Style::default().fg(SUBTLE)),
-        Span::styled("◐ ", Style::default().fg(FOAM)),
-        Span::styled("wait  ", Style::default().fg(SUBTLE)),
-        Span::styled("✓ ", Style::default().fg(SUBTLE)),
-        Span::styled("idle  ", Style::default().fg(SUBTLE)),
-        Span::styled("○ ", Style::default().fg(MUTED)),
-        Span::styled("hist", Style::default().fg(SUBTLE)),
+        Span::styled("⠋ ", theme.accent),
+        Span::styled("work  ", theme.subtle),
+        Span::styled("◐ ", theme.info),
+        Span::styled("wait  ", theme.subtle),
+        Span::styled("✓ ", theme.subtle),
+        Span::styled("idle  ", theme.subtle),
+        Span::styled("◌ ", theme.accent),
+        Span::styled("ended  ", theme.subtle),
+        Span::styled("○ ", theme.muted),
+        Span::styled("hist", theme.subtle),
     ])).alignment(Alignment::Center);
     frame.render_widget(legend, legend_area);
 
     // Compact help bar
-    let help = Paragraph::new(Line::from(vec![
-        Span::styled("1-9", Style::default().fg(FOAM)),
-        Span::styled(" jump ", Style::default().fg(SUBTLE)),
-        Span::styled("j/k", Style::default().fg(FOAM)),
-        Span::styled(" nav ", Style::default().fg(SUBTLE)),
-        Span::styled("↵/r", Style::default().fg(FOAM)),
-        Span::styled(" go ", Style::default().fg(SUBTLE)),
-        Span::styled("x", Style::default().fg(FOAM)),
-        Span::styled(" kill ", Style::default().fg(SUBTLE)),
-        Span::styled("D", Style::default().fg(FOAM)),
-        Span::styled(" del ", Style::default().fg(SUBTLE)),
-        Span::styled("Tab", Style::default().fg(FOAM)),
-        Span::styled(" view ", Style::default().fg(SUBTLE)),
-        Span::styled("q", Style::default().fg(FOAM)),
-        Span::styled(" quit", Style::default().fg(SUBTLE)),
-    ])).alignment(Alignment::Center);
-    frame.render_widget(help, help_area);
+    let mut help_spans = Vec::new();
+    for (keys, label) in HELP_BAR {
+        let keys = match keys {
+            Bound(actions) => actions.iter()
+                .filter_map(|&a| app.keymap.first_label(a))
+                .collect::<Vec<_>>()
+                .join("/"),
+            AllBound(action) => app.keymap.label(*action),
+            Fixed(text) => text.to_string(),
+        };
+        if keys.is_empty() {
+            continue;
+        }
+        if !help_spans.is_empty() {
+            help_spans.push(Span::raw(" "));
+        }
+        help_spans.push(Span::styled(keys, theme.info));
+        help_spans.push(Span::styled(format!(" {}", label), theme.subtle));
+    }
+    let help = Paragraph::new(Line::from(help_spans)).alignment(Alignment::Center);
+    if let Some(ref flash) = app.flash {
+        // One-shot status message takes over the bar for a single render
+        let flash = Paragraph::new(Span::styled(flash.as_str(), theme.accent))
+            .alignment(Alignment::Center);
+        frame.render_widget(Clear, help_area);
+        frame.render_widget(flash, help_area);
+    } else if !app.filter_input && app.filter_query.is_empty() && app.command_input.is_none() {
+        frame.render_widget(help, help_area);
+    }
+}
+
+/// Name of a session's repo and how many listed sessions share it
+fn render_group_header(frame: &mut Frame, app: &App, session: &Session, area: Rect) {
+    let dir = app.repo_dir(session);
+    let count = app.sessions.iter().filter(|s| app.repo_dir(s) == dir).count();
+    let name = match dir {
+        Some(dir) => repo_name(dir),
+        None => "not in a git repo".to_string(),
+    };
+    let style = if dir.is_some() { app.theme.accent } else { app.theme.muted };
+    let line = Line::from(vec![
+        Span::styled(name, style.bold()),
+        Span::styled(format!(" · {}", count), app.theme.subtle),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// Repo name from its git common dir: `/src/app/.git` and `/srv/app.git` are both `app`
+fn repo_name(git_dir: &str) -> String {
+    let path = Path::new(git_dir);
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(".git") => path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()),
+        other => other,
+    };
+    let name = name.unwrap_or(git_dir);
+    name.strip_suffix(".git").unwrap_or(name).to_string()
+}
+
+/// Message for an empty list. In All view there should at least be
+/// historical sessions, so name the directories searched (and any missing).
+fn empty_message(view_mode: ViewMode, filtered: bool, dirs: &[PathBuf]) -> String {
+    if filtered {
+        return "No matching sessions".to_string();
+    }
+    match view_mode {
+        ViewMode::Running => "No active sessions".to_string(),
+        ViewMode::All => {
+            let dirs: Vec<String> = dirs.iter()
+                .map(|d| if d.is_dir() {
+                    d.display().to_string()
+                } else {
+                    format!("{} (does not exist)", d.display())
+                })
+                .collect();
+            format!("No sessions found in {}", dirs.join(", "))
+        }
+    }
+}
+
+/// Session counts by status for the list border, e.g. ` 3 working · 2 waiting · 5 idle · 12 total `
+fn status_summary(theme: &Theme, sessions: &[Session], config: &Config) -> Line<'static> {
+    let running = || sessions.iter().filter(|s| s.is_running);
+    let working = running()
+        .filter(|s| matches!(s.status, SessionStatus::Thinking | SessionStatus::Processing))
+        .count();
+    let waiting = running().filter(|s| s.status == SessionStatus::Waiting).count();
+    let idle = running().filter(|s| s.status == SessionStatus::Idle).count();
+    let historical = sessions.len() - running().count();
+
+    let mut counts = vec![
+        (working, "working", theme.accent),
+        (waiting, "waiting", theme.info),
+        (idle, "idle", theme.subtle),
+    ];
+    if historical > 0 {
+        counts.push((historical, "hist", theme.muted));
+    }
+
+    let label = theme.subtle;
+    let mut spans = vec![Span::raw(" ")];
+    for (count, name, style) in counts {
+        spans.push(Span::styled(count.to_string(), style));
+        spans.push(Span::styled(format!(" {} · ", name), label));
+    }
+    spans.push(Span::styled(sessions.len().to_string(), theme.text));
+    spans.push(Span::styled(" total ", label));
+
+    // Token totals over each session's whole log, and an estimate of their
+    // cost from sessions whose model has a price
+    let input: u64 = sessions.iter().map(|s| s.usage.all_input()).sum();
+    let output: u64 = sessions.iter().map(|s| s.usage.output_tokens).sum();
+    if input + output > 0 {
+        spans.push(Span::styled(
+            format!("· {}↑/{}↓ ", format_tokens(input), format_tokens(output)),
+            label,
+        ));
+        let priced: Vec<f64> = sessions.iter()
+            .filter_map(|s| {
+                let rate = config.model_rate(s.model.as_deref()?)?;
+                Some(rate.cost(&s.usage))
+            })
+            .collect();
+        if !priced.is_empty() {
+            spans.push(Span::styled(format!("~${:.2} ", priced.iter().sum::<f64>()), theme.text));
+        }
+    }
+    Line::from(spans)
+}
+
+/// Detection counts for the --debug pane, to tell a wrong projects
+/// directory from failed process detection or pane mapping
+fn diagnostics_lines(app: &App, width: u16) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!(
+        "multiplexer    {}",
+        if app.mux.is_multiplexer() { app.mux.name() } else { "none" },
+    ))];
+    for root in session::projects_dirs() {
+        let missing = if root.is_dir() { "" } else { " (missing)" };
+        // Borders, label, and the missing marker
+        let max = (width as usize).saturating_sub(2 + 15 + missing.len());
+        let root = middle_ellipsis(&root.to_string_lossy(), max);
+        lines.push(Line::from(format!("projects dir   {}{}", root, missing)));
+    }
+    match app.diagnostics {
+        Some(ref d) => lines.extend([
+            Line::from(format!("project dirs   {}", d.project_dirs)),
+            Line::from(format!("processes      {} claude", d.processes)),
+            Line::from(format!("  → projects   {}", d.matched_projects)),
+            Line::from(format!("  → sessions   {} with a JSONL file", d.parsed_sessions)),
+            Line::from(format!("  → panes      {} (of {} panes listed)", d.mapped_panes, d.panes_seen)),
+        ]),
+        None => lines.push(Line::from("no scan yet")),
+    }
+    for warning in &app.index_warnings {
+        let max = (width as usize).saturating_sub(2 + 15);
+        lines.push(Line::from(format!("damaged index  {}", middle_ellipsis(warning, max))));
+    }
+    lines
+}
+
+fn render_diagnostics(frame: &mut Frame, theme: &Theme, area: Rect, lines: Vec<Line<'static>>) {
+    let block = Block::default()
+        .title(" Diagnostics ")
+        .title_style(theme.accent.bold())
+        .borders(Borders::ALL)
+        .border_style(theme.subtle);
+    let paragraph = Paragraph::new(lines)
+        .style(theme.muted)
+        .block(block);
+    frame.render_widget(paragraph, area);
+}
+
+/// `:` command line while it's open
+fn render_command_bar(frame: &mut Frame, theme: &Theme, area: Rect, input: &str) {
+    let line = Line::from(vec![
+        Span::styled(":", theme.info),
+        Span::styled(input.to_string(), theme.text),
+        Span::styled("▏", theme.accent),
+        Span::styled("  ↵ run  Esc cancel", theme.subtle),
+    ]);
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// Filter query line: editable prompt while typing, summary once applied
+fn render_filter_bar(frame: &mut Frame, theme: &Theme, area: Rect, query: &str, editing: bool) {
+    let line = if editing {
+        Line::from(vec![
+            Span::styled("/", theme.info),
+            Span::styled(query.to_string(), theme.text),
+            Span::styled("▏", theme.accent),
+            Span::styled("  ↵ keep  Esc clear", theme.subtle),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled("filter: ", theme.subtle),
+            Span::styled(query.to_string(), theme.info),
+            Span::styled("  / edit  Esc clear", theme.subtle),
+        ])
+    };
+    frame.render_widget(Paragraph::new(line).alignment(Alignment::Center), area);
+}
+
+/// Log search prompt while typing the query
+fn render_log_search_bar(frame: &mut Frame, theme: &Theme, area: Rect, query: &str) {
+    let line = Line::from(vec![
+        Span::styled("log search: ", theme.info),
+        Span::styled(query.to_string(), theme.text),
+        Span::styled("▏", theme.accent),
+        Span::styled("  ↵ search  n/N next/prev  Esc clear", theme.subtle),
+    ]);
+    frame.render_widget(Paragraph::new(line).alignment(Alignment::Center), area);
+}
+
+/// Cut `s` to at most `max` display columns (wide characters count as
+/// two), marking the cut with `…`
+fn truncate_width(s: &str, max: usize) -> String {
+    if s.width() <= max {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w >= max {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// Shorten a path to `max` chars by cutting out its middle, keeping the
+/// leading directories and the trailing name (`/Users/me/…/my-project`).
+/// Cuts prefer `/` boundaries; paths that fit are returned unchanged.
+fn middle_ellipsis(path: &str, max: usize) -> String {
+    let chars: Vec<char> = path.chars().collect();
+    if chars.len() <= max {
+        return path.to_string();
+    }
+    if max < 2 {
+        return "…".repeat(max);
+    }
+
+    let budget = max - 1;
+    let mut head = budget / 3;
+    let mut tail = budget - head;
+    // Snap to separators unless that throws away more than half the share
+    if let Some(i) = chars[..head].iter().rposition(|&c| c == '/') {
+        if i + 1 >= head / 2 {
+            head = i + 1;
+        }
+    }
+    let tail_start = chars.len() - tail;
+    if let Some(i) = chars[tail_start..].iter().position(|&c| c == '/') {
+        if tail - i >= tail / 2 {
+            tail -= i;
+        }
+    }
+
+    let head: String = chars[..head].iter().collect();
+    let tail: String = chars[chars.len() - tail..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+/// Collapse newlines and runs of whitespace into single spaces
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 /// Format seconds into human-readable relative time
 fn format_relative_time(secs: u64) -> String {
-    if secs < 60 {
+    if secs < 3 {
+        "now".to_string()
+    } else if secs < 60 {
         format!("{}s", secs)
+    } else if secs < 120 {
+        format!("1m{:02}s", secs - 60)
     } else if secs < 3600 {
         format!("{}m", secs / 60)
     } else if secs < 86400 {
@@ -140,25 +636,64 @@ fn format_relative_time(secs: u64) -> String {
     }
 }
 
-fn render_session_card(frame: &mut Frame, session: &Session, area: Rect, selected: bool, index: usize) {
+/// Format a duration with two units (e.g. 45s, 1m20s, 2h05m)
+fn format_elapsed(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// Format a byte count compactly (e.g. 142MB, 2.3GB)
+fn format_bytes(n: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    const GB: u64 = 1024 * MB;
+    if n >= GB {
+        format!("{:.1}GB", n as f64 / GB as f64)
+    } else {
+        format!("{}MB", n / MB)
+    }
+}
+
+/// Format a token count compactly (e.g. 950, 12k, 1.2M)
+fn format_tokens(n: u64) -> String {
+    if n < 1000 {
+        format!("{}", n)
+    } else if n < 1_000_000 {
+        format!("{}k", n / 1000)
+    } else {
+        format!("{:.1}M", n as f64 / 1_000_000.0)
+    }
+}
+
+fn render_session_card(frame: &mut Frame, app: &App, session: &Session, area: Rect, selected: bool, index: usize) {
+    let theme = &app.theme;
     // Historical sessions get a different icon
-    let (status_icon, status_color) = if !session.is_running {
-        ("○", MUTED)  // Historical/not running
+    let (status_icon, status_style) = if session.is_sidechain {
+        ("⊂", theme.highlight)  // Sub-agent sidechain
+    } else if session.just_ended {
+        ("◌", theme.accent)  // Exited within the last few minutes
+    } else if session.project_missing {
+        ("⚠", theme.alert)  // Project directory deleted or moved
+    } else if !session.is_running {
+        ("○", theme.muted)  // Historical/not running
     } else {
+        let spinner = SPINNER_FRAMES[(app.frame_count % SPINNER_FRAMES.len() as u64) as usize];
         match session.status {
-            SessionStatus::Thinking => ("↻", GOLD),      // working/thinking
-            SessionStatus::Processing => ("↻", PINE),    // working/processing
-            SessionStatus::Waiting => ("◐", FOAM),       // waiting for input
-            SessionStatus::Idle => ("✓", SUBTLE),        // idle/done
+            SessionStatus::Thinking => (spinner, theme.accent),      // working/thinking
+            SessionStatus::Processing => (spinner, theme.working),    // working/processing
+            SessionStatus::Waiting => ("◐", theme.info),       // waiting for input
+            SessionStatus::Idle => ("✓", theme.subtle),        // idle/done
         }
     };
 
-    let bg_color = if selected { OVERLAY } else { Color::Reset };
-
     // For selected: simple solid background fill
     if selected {
         let fill = " ".repeat(area.width as usize);
-        let fill_style = Style::default().bg(bg_color);
+        let fill_style = theme.selection;
         for row in 0..area.height {
             frame.render_widget(
                 Paragraph::new(fill.clone()).style(fill_style),
@@ -171,54 +706,111 @@ fn render_session_card(frame: &mut Frame, session: &Session, area: Rect, selecte
 
     let width = inner.width as usize;
 
+    // Index column widens to two digits once numbers past 9 are reachable
+    let index_width = if app.sessions.len() > 9 { 2 } else { 1 };
+    let indent = 3 + index_width;
+
     // Line 1: [index] status icon + project name + [window#] + relative time
     if inner.height >= 1 {
         let line1_area = Rect::new(inner.x, inner.y, inner.width, 1);
 
         // Dim historical sessions slightly
-        let text_color = if session.is_running { TEXT } else { MUTED };
-        let name_style = if selected {
-            Style::default().bold().fg(text_color)
-        } else {
-            Style::default().fg(text_color)
-        };
+        let text_style = if session.is_running { theme.text } else { theme.muted };
+        let name_style = if selected { text_style.bold() } else { text_style };
 
-        // Index number (1-9, then nothing)
-        let index_str = if index < 9 {
-            format!("{}", index + 1)
+        // Index number (1-99, then nothing)
+        let index_str = if index < 99 {
+            format!("{:>w$}", index + 1, w = index_width)
         } else {
-            " ".to_string()
+            " ".repeat(index_width)
         };
 
-        // Window number badge (compact)
+        // Window badge: name when it has one, else the index
         let window_badge = session.tmux_location.as_ref()
-            .map(|l| format!(":{}", l.window_index))
+            .map(|l| {
+                let label = truncate_width(&l.window_label(), MAX_WINDOW_LABEL_LEN);
+                format!(":{}{}", label, if session.tmux_stale { "?" } else { "" })
+            })
+            .unwrap_or_default();
+
+        // Git branch (dimmed, long names truncated)
+        let branch_badge = session.git_branch.as_ref()
+            .map(|b| format!(" {}", truncate_width(b, MAX_BRANCH_LEN)))
             .unwrap_or_default();
 
-        // Relative time
-        let time_str = format_relative_time(session.last_activity_secs);
-        let time_width = time_str.len() + 1;
+        // Compact cards have no message line, so spell out the status
+        let status_badge = if !app.compact_cards {
+            String::new()
+        } else if session.just_ended {
+            " ended".to_string()
+        } else if !session.is_running {
+            " historical".to_string()
+        } else {
+            format!(" {}", session.status.to_string().to_lowercase())
+        };
+
+        let pin_badge = if app.is_pinned(&session.id) { "★ " } else { "" };
+
+        // "Needs me" badge
+        let attention_badge = if app.show_attention && session.needs_attention { "‼ " } else { "" };
+
+        // Outstanding todos from the latest TodoWrite
+        let todo_badge = match session.pending_todos {
+            Some(n) if n > 0 => format!("☐ {} ", n),
+            _ => String::new(),
+        };
+
+        // Token usage badge (running sessions with usage data)
+        let token_badge = if session.is_running && session.usage.all_input() + session.usage.output_tokens > 0 {
+            format!("{}↑/{}↓ ", format_tokens(session.usage.all_input()), format_tokens(session.usage.output_tokens))
+        } else {
+            String::new()
+        };
 
-        // Truncate project name if too long
-        let badge_len = window_badge.chars().count();
-        let max_name_len = width.saturating_sub(6 + time_width + badge_len);
-        let name = if session.project_name.len() > max_name_len {
-            format!("{}…", &session.project_name[..max_name_len.saturating_sub(1)])
+        // Relative time (or kill in progress, or how long it's been busy, or
+        // with `C` how long ago a historical session was created)
+        let killing = app.is_being_killed(&session.id);
+        let busy_since = app.busy_since.get(&session.id);
+        let created = session::created_age_secs(session).filter(|_| app.show_created_time);
+        let (time_str, time_style) = if killing {
+            ("killing…".to_string(), theme.alert)
+        } else if let Some(since) = busy_since {
+            (format!("⏱ {}", format_elapsed(since.elapsed().as_secs())), status_style)
+        } else if let Some(secs) = created {
+            (format_relative_time(secs), theme.subtle)
         } else {
-            session.project_name.clone()
+            (format_relative_time(session.last_activity_secs), theme.subtle)
         };
+        let time_width = time_str.width() + 1;
+
+        // Truncate project name if too long (by display width)
+        let badge_len = window_badge.width()
+            + branch_badge.width()
+            + status_badge.width()
+            + pin_badge.width()
+            + attention_badge.width()
+            + todo_badge.width()
+            + token_badge.width();
+        let max_name_len = width.saturating_sub(indent + 2 + time_width + badge_len);
+        let name = truncate_width(&session.project_name, max_name_len);
 
         // Calculate padding for right-aligned time
-        let used_width = 4 + name.chars().count() + badge_len;
+        let used_width = indent + name.width() + badge_len;
         let padding = width.saturating_sub(used_width + time_width);
 
         let line1 = Line::from(vec![
-            Span::styled(format!("{} ", index_str), Style::default().fg(SUBTLE)),
-            Span::styled(format!("{} ", status_icon), Style::default().fg(status_color)),
+            Span::styled(format!("{} ", index_str), theme.subtle),
+            Span::styled(format!("{} ", status_icon), status_style),
+            Span::styled(pin_badge, theme.accent),
+            Span::styled(attention_badge, theme.alert.bold()),
             Span::styled(name, name_style),
-            Span::styled(window_badge, Style::default().fg(SUBTLE)),
+            Span::styled(window_badge, theme.subtle),
+            Span::styled(branch_badge, theme.muted),
+            Span::styled(status_badge, status_style),
             Span::styled(" ".repeat(padding), Style::default()),
-            Span::styled(time_str, Style::default().fg(SUBTLE)),
+            Span::styled(todo_badge, theme.info),
+            Span::styled(token_badge, theme.subtle),
+            Span::styled(time_str, time_style),
         ]);
         frame.render_widget(Paragraph::new(line1), line1_area);
     }
@@ -227,8 +819,8 @@ fn render_session_card(frame: &mut Frame, session: &Session, area: Rect, selecte
     if inner.height >= 2 {
         let line2_area = Rect::new(inner.x, inner.y + 1, inner.width, 1);
 
-        // For historical sessions, prefer first_prompt; for running, use last_message
-        let message = if !session.is_running {
+        // For historical sessions, prefer first_prompt unless toggled; for running, use last_message
+        let message = if !session.is_running && !app.show_last_message {
             session.first_prompt.as_deref()
                 .or(session.last_message.as_deref())
                 .unwrap_or("—")
@@ -236,25 +828,314 @@ fn render_session_card(frame: &mut Frame, session: &Session, area: Rect, selecte
             session.last_message.as_deref().unwrap_or("—")
         };
 
-        // Clean up message: remove newlines, collapse whitespace
-        let clean_msg: String = message
-            .chars()
-            .map(|c| if c.is_whitespace() { ' ' } else { c })
-            .collect::<String>()
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        let max_len = width.saturating_sub(6);
-        let truncated = if clean_msg.chars().count() > max_len {
-            format!("    {}…", clean_msg.chars().take(max_len.saturating_sub(1)).collect::<String>())
-        } else {
-            format!("    {}", clean_msg)
-        };
+        let clean_msg = one_line(message);
+
+        let max_len = width.saturating_sub(indent + 2);
+        let pad = " ".repeat(indent);
+        let truncated = format!("{}{}", pad, truncate_width(&clean_msg, max_len));
 
         // Dim historical session messages
-        let msg_color = if session.is_running { MUTED } else { SUBTLE };
-        let line2 = Paragraph::new(truncated).style(Style::default().fg(msg_color));
+        let msg_style = if session.is_running { theme.muted } else { theme.subtle };
+        let line2 = Paragraph::new(truncated).style(msg_style);
         frame.render_widget(line2, line2_area);
     }
 }
+
+/// Centered rect of at most the given size within `area`
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Full-screen list of every keybinding, sections split over two columns
+fn render_help(frame: &mut Frame, theme: &Theme, keymap: &Keymap) {
+    let section_lines = |sections: &[(&str, &[(HelpKeys, &str)])]| -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for (title, keys) in sections {
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::styled(title.to_string(), theme.accent.bold()));
+            for (keys, action) in keys.iter() {
+                let key = match keys {
+                    Bound(actions) => actions.iter()
+                        .map(|&a| keymap.label(a))
+                        .filter(|l| !l.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" / "),
+                    AllBound(action) => keymap.label(*action),
+                    Fixed(text) => text.to_string(),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<10} ", key), theme.info),
+                    Span::styled(action.to_string(), theme.text),
+                ]));
+            }
+        }
+        lines
+    };
+
+    let area = frame.area();
+    let block = Block::default()
+        .title(" Keybindings ")
+        .title_style(theme.accent.bold())
+        .title_bottom(Line::styled(" ? / Esc close ", theme.subtle))
+        .borders(Borders::ALL)
+        .border_style(theme.highlight)
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let (left, right) = HELP_SECTIONS.split_at(HELP_SECTIONS.len() / 2);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    frame.render_widget(Paragraph::new(section_lines(left)), columns[0]);
+    frame.render_widget(Paragraph::new(section_lines(right)), columns[1]);
+}
+
+/// Modal showing the process chain from Claude up to its shell and beyond
+fn render_process_tree(frame: &mut Frame, theme: &Theme, ancestors: &[ProcessAncestor], tmux_target: Option<&str>) {
+    let mut lines: Vec<Line> = Vec::new();
+
+    if ancestors.is_empty() {
+        lines.push(Line::styled("Process not found", theme.muted));
+    }
+
+    for (depth, ancestor) in ancestors.iter().enumerate() {
+        let branch = if depth == 0 {
+            String::new()
+        } else {
+            format!("{}└─ ", "  ".repeat(depth - 1))
+        };
+        let mut spans = vec![
+            Span::styled(branch, theme.subtle),
+            Span::styled(ancestor.name.clone(), theme.text),
+            Span::styled(format!(" {}", ancestor.pid), theme.muted),
+        ];
+        if depth == 0 {
+            spans.push(Span::styled("  ← claude", theme.accent));
+        }
+        if ancestor.is_shell {
+            let pane = tmux_target
+                .map(|t| format!(" → tmux {}", t))
+                .unwrap_or_else(|| " → no tmux pane".to_string());
+            spans.push(Span::styled(format!("  ← shell{}", pane), theme.info));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    if !ancestors.is_empty() && !ancestors.iter().any(|a| a.is_shell) {
+        lines.push(Line::from(""));
+        lines.push(Line::styled("No known shell found in ancestry", theme.alert));
+    }
+
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(70, height, frame.area());
+    let block = Block::default()
+        .title(" Process tree ")
+        .title_style(theme.accent.bold())
+        .title_bottom(Line::styled(" Esc close ", theme.subtle))
+        .borders(Borders::ALL)
+        .border_style(theme.highlight)
+        .padding(Padding::horizontal(1));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Modal listing everything known about a session
+fn render_detail(frame: &mut Frame, theme: &Theme, session: &Session, cpu_history: Option<&VecDeque<f32>>) {
+    let dash = || "—".to_string();
+    let ago = |secs: u64| match format_relative_time(secs).as_str() {
+        "now" => "just now".to_string(),
+        t => format!("{} ago", t),
+    };
+    let created = session.created_at.as_deref()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .zip(session::created_age_secs(session))
+        .map(|(t, secs)| format!("{} ({})", t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"), ago(secs)))
+        .unwrap_or_else(dash);
+    let active = chrono::Local::now() - chrono::Duration::seconds(session.last_activity_secs as i64);
+    let active = format!("{} ({})", active.format("%Y-%m-%d %H:%M:%S"), ago(session.last_activity_secs));
+    let usage = &session.usage;
+    let tokens = if usage.all_input() + usage.output_tokens > 0 {
+        format!(
+            "{} in ({} cached) / {} out",
+            format_tokens(usage.all_input()),
+            format_tokens(usage.cache_read_input_tokens),
+            format_tokens(usage.output_tokens),
+        )
+    } else {
+        dash()
+    };
+
+    // Value column: popup width less borders, padding, and labels
+    let area_width = frame.area().width.saturating_sub(4);
+    let path_width = (area_width as usize).saturating_sub(2 + 2 + 10);
+    let path = |p: &str| middle_ellipsis(p, path_width);
+
+    let rows = [
+        ("Project", path(&session.project_path)),
+        ("Session", session.id.clone()),
+        ("Prompt", session.first_prompt.as_deref().map(one_line).unwrap_or_else(dash)),
+        ("Root", session.projects_root.as_deref().map(path).unwrap_or_else(dash)),
+        ("Branch", session.git_branch.clone().unwrap_or_else(dash)),
+        ("Status", if session.is_running { session.status.to_string() } else { "Historical".to_string() }),
+        ("Created", created),
+        ("Active", active),
+        ("Messages", session.message_count.map(|c| c.to_string()).unwrap_or_else(dash)),
+        ("Model", session.model.clone().unwrap_or_else(dash)),
+        ("Tokens", tokens),
+        ("Todos", session.pending_todos.map(|n| format!("{} pending", n)).unwrap_or_else(dash)),
+        ("tmux", session.tmux_target.clone().unwrap_or_else(dash)),
+        ("PID", session.pid.map(|p| p.to_string()).unwrap_or_else(dash)),
+        ("CPU", match cpu_history {
+            Some(samples) if session.is_running => format!("{:.1}%  {}", session.cpu_usage, cpu_sparkline(samples)),
+            _ if session.is_running => format!("{:.1}%", session.cpu_usage),
+            _ => dash(),
+        }),
+        ("Memory", if session.memory_bytes > 0 { format_bytes(session.memory_bytes) } else { dash() }),
+    ];
+
+    let lines: Vec<Line> = rows.into_iter()
+        .map(|(label, value)| Line::from(vec![
+            Span::styled(format!("{:<10}", label), theme.subtle),
+            Span::styled(value, theme.text),
+        ]))
+        .collect();
+
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(area_width, height, frame.area());
+    let block = Block::default()
+        .title(format!(" {} ", session.project_name))
+        .title_style(theme.accent.bold())
+        .title_bottom(Line::styled(" e export · Esc close ", theme.subtle))
+        .borders(Borders::ALL)
+        .border_style(theme.highlight)
+        .padding(Padding::horizontal(1));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// CPU samples as a braille sparkline, two samples per character and four
+/// dot rows for 0-100% (any usage at all lights the bottom row); newest right
+fn cpu_sparkline(samples: &VecDeque<f32>) -> String {
+    // Dot bits from the bottom row up, for the left and right column
+    const LEFT: [u32; 4] = [0x40, 0x04, 0x02, 0x01];
+    const RIGHT: [u32; 4] = [0x80, 0x20, 0x10, 0x08];
+    let level = |cpu: f32| (cpu.clamp(0.0, 100.0) / 25.0).ceil() as usize;
+    let dots = |column: &[u32; 4], cpu: f32| column[..level(cpu)].iter().sum::<u32>();
+
+    // Pad an odd count at the old end so the newest sample fills a right column
+    let padding = (samples.len() % 2 == 1).then_some(0.0);
+    let padded: Vec<f32> = padding.into_iter().chain(samples.iter().copied()).collect();
+    padded.chunks(2)
+        .map(|pair| char::from_u32(0x2800 + dots(&LEFT, pair[0]) + dots(&RIGHT, pair[1])).unwrap_or(' '))
+        .collect()
+}
+
+/// Small centered y/n dialog for destructive actions
+fn render_confirm(frame: &mut Frame, theme: &Theme, message: &str) {
+    let width = (message.chars().count() as u16 + 6).max(24);
+    let area = centered_rect(width, 5, frame.area());
+    let block = Block::default()
+        .title(" Confirm ")
+        .title_style(theme.alert.bold())
+        .borders(Borders::ALL)
+        .border_style(theme.alert)
+        .padding(Padding::horizontal(1));
+
+    let text = vec![
+        Line::styled(message.to_string(), theme.text),
+        Line::from(vec![
+            Span::styled("y", theme.info),
+            Span::styled(" yes  ", theme.subtle),
+            Span::styled("n", theme.info),
+            Span::styled(" no", theme.subtle),
+        ]),
+    ];
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(text).alignment(Alignment::Center).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_time_is_finest_for_recent_activity() {
+        assert_eq!(format_relative_time(0), "now");
+        assert_eq!(format_relative_time(2), "now");
+        assert_eq!(format_relative_time(45), "45s");
+        assert_eq!(format_relative_time(90), "1m30s");
+        assert_eq!(format_relative_time(65), "1m05s");
+        assert_eq!(format_relative_time(150), "2m");
+        assert_eq!(format_relative_time(7200), "2h");
+        assert_eq!(format_relative_time(3 * 86400), "3d");
+    }
+
+    #[test]
+    fn empty_message_names_the_projects_dir_in_all_view() {
+        let dirs = [PathBuf::from("/nonexistent/projects")];
+        assert_eq!(empty_message(ViewMode::Running, false, &dirs), "No active sessions");
+        assert_eq!(empty_message(ViewMode::All, true, &dirs), "No matching sessions");
+        assert_eq!(
+            empty_message(ViewMode::All, false, &dirs),
+            "No sessions found in /nonexistent/projects (does not exist)",
+        );
+    }
+
+    #[test]
+    fn cpu_sparkline_packs_two_samples_per_braille_char() {
+        let samples = |v: &[f32]| v.iter().copied().collect::<VecDeque<f32>>();
+        assert_eq!(cpu_sparkline(&samples(&[0.0, 100.0])), "⢸");
+        assert_eq!(cpu_sparkline(&samples(&[100.0, 0.0, 0.5, 20.0])), "⡇⣀");
+        // Odd count: the oldest sample shares a char with blank padding
+        assert_eq!(cpu_sparkline(&samples(&[100.0])), "⢸");
+        assert_eq!(cpu_sparkline(&samples(&[])), "");
+    }
+
+    #[test]
+    fn repo_name_handles_plain_and_bare_git_dirs() {
+        assert_eq!(repo_name("/src/app/.git"), "app");
+        assert_eq!(repo_name("/srv/app.git"), "app");
+    }
+
+    #[test]
+    fn truncate_width_counts_wide_characters_as_two_columns() {
+        assert_eq!(truncate_width("プロジェクト", 12), "プロジェクト");
+        assert_eq!(truncate_width("プロジェクト", 7), "プロジ…");
+        assert_eq!(truncate_width("プロジェクト", 6), "プロ…");
+        assert_eq!(truncate_width("café-🦀-app", 7), "café-…");
+        assert_eq!(truncate_width("abc", 0), "");
+    }
+
+    #[test]
+    fn middle_ellipsis_keeps_both_ends_at_separators() {
+        let path = "/Users/me/projects/clients/acme/my-project";
+        let short = middle_ellipsis(path, 24);
+        assert_eq!(short, "/Users/…/acme/my-project");
+        assert!(short.chars().count() <= 24);
+    }
+
+    #[test]
+    fn middle_ellipsis_leaves_short_paths_and_counts_chars() {
+        assert_eq!(middle_ellipsis("/tmp/x", 6), "/tmp/x");
+        assert_eq!(middle_ellipsis("/home/ünïcödé/prøjekt", 30), "/home/ünïcödé/prøjekt");
+        let short = middle_ellipsis("/home/ünïcödé/dïrëctory/prøjekt", 16);
+        assert!(short.chars().count() <= 16);
+        assert!(short.ends_with("prøjekt"));
+        assert_eq!(middle_ellipsis("/abc", 1), "…");
+    }
+}
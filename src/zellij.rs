@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::tmux::TmuxLocation;
+
+/// Get mapping of shell PID -> Zellij tab, for the given shell PIDs.
+///
+/// Zellij has no CLI to list panes with their PIDs, so this reads the
+/// `ZELLIJ_PANE_ID`/`ZELLIJ_SESSION_NAME` each shell inherited from /proc
+/// (Linux only), then finds the pane's tab in `zellij action dump-layout`
+/// by the shell's working directory. The window index is the 1-based tab
+/// position, or 0 when the tab couldn't be found.
+pub fn get_pane_map(shell_pids: &[u32]) -> HashMap<u32, TmuxLocation> {
+    let mut map = HashMap::new();
+    // Tabs of each Zellij session, dumped once per refresh
+    let mut layouts: HashMap<String, Vec<LayoutTab>> = HashMap::new();
+
+    for &pid in shell_pids {
+        let proc_dir = Path::new("/proc").join(pid.to_string());
+        // Unreadable for other users' processes; skip those
+        let environ = match fs::read(proc_dir.join("environ")) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+
+        let mut pane_id = None;
+        let mut session = None;
+        for var in environ.split(|b| *b == 0) {
+            let var = String::from_utf8_lossy(var);
+            if let Some(id) = var.strip_prefix("ZELLIJ_PANE_ID=") {
+                pane_id = id.parse::<u32>().ok();
+            } else if let Some(name) = var.strip_prefix("ZELLIJ_SESSION_NAME=") {
+                session = Some(name.to_string());
+            }
+        }
+
+        if let (Some(pane_id), Some(session)) = (pane_id, session) {
+            let tabs = layouts.entry(session.clone()).or_insert_with(|| dump_layout(&session));
+            let cwd = fs::read_link(proc_dir.join("cwd")).ok();
+            let tab = cwd.and_then(|cwd| tabs.iter().position(|t| t.cwds.contains(&cwd)));
+            map.insert(pid, TmuxLocation {
+                window_index: tab.map_or(0, |i| i as u32 + 1),
+                window_name: tab.map_or_else(|| format!("pane {}", pane_id), |i| tabs[i].name.clone()),
+                session,
+                pane_id: None,
+            });
+        }
+    }
+
+    map
+}
+
+/// A tab in a dumped layout and the working directories of its panes
+#[derive(Debug, PartialEq)]
+struct LayoutTab {
+    name: String,
+    cwds: Vec<PathBuf>,
+}
+
+/// Tabs of a Zellij session, or none if its layout can't be dumped
+fn dump_layout(session: &str) -> Vec<LayoutTab> {
+    run(&["--session", session, "action", "dump-layout"])
+        .map(|kdl| parse_layout(&kdl))
+        .unwrap_or_default()
+}
+
+/// Read the `tab name="…" { pane cwd="…" … }` blocks of a KDL layout dump.
+/// Pane cwds may be relative to the layout's top-level `cwd`.
+fn parse_layout(kdl: &str) -> Vec<LayoutTab> {
+    let quoted = |line: &str, key: &str| -> Option<String> {
+        let start = line.find(key)? + key.len();
+        let end = line[start..].find('"')?;
+        Some(line[start..start + end].to_string())
+    };
+
+    let mut base = PathBuf::new();
+    let mut tabs: Vec<LayoutTab> = Vec::new();
+    let mut in_tab = false;
+    let mut depth = 0usize;
+    for line in kdl.lines().map(str::trim) {
+        // Children of `layout {}` are at depth 1: the base cwd, tabs, templates
+        if depth == 1 {
+            in_tab = line.starts_with("tab ") || line == "tab {";
+            if in_tab {
+                let name = quoted(line, "name=\"").unwrap_or_else(|| format!("Tab #{}", tabs.len() + 1));
+                tabs.push(LayoutTab { name, cwds: Vec::new() });
+            } else if let Some(cwd) = line.strip_prefix("cwd \"").and_then(|c| c.strip_suffix('"')) {
+                base = PathBuf::from(cwd);
+            }
+        } else if in_tab && depth > 1 && line.starts_with("pane") {
+            if let (Some(cwd), Some(tab)) = (quoted(line, "cwd=\""), tabs.last_mut()) {
+                tab.cwds.push(base.join(cwd));
+            }
+        }
+        depth += line.matches('{').count();
+        depth = depth.saturating_sub(line.matches('}').count());
+    }
+    tabs
+}
+
+/// Show the `index`th (1-based) tab of the current Zellij session
+pub fn go_to_tab(index: u32) -> Result<(), String> {
+    run(&["action", "go-to-tab", &index.to_string()]).map(|_| ())
+}
+
+// Last failure of a zellij command whose caller only learns that it failed
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Take the last recorded zellij failure, if any, for showing once
+pub fn take_error() -> Option<String> {
+    LAST_ERROR.lock().unwrap().take()
+}
+
+fn record_error(error: String) {
+    *LAST_ERROR.lock().unwrap() = Some(error);
+}
+
+/// Run a zellij command, returning its stdout, or on failure the first line
+/// of its stderr prefixed with the subcommand
+fn run(args: &[&str]) -> Result<String, String> {
+    let name = args.iter().skip_while(|a| **a != "action").nth(1).copied().unwrap_or("action");
+    let output = Command::new("zellij")
+        .args(args)
+        .output()
+        .map_err(|e| format!("can't run zellij: {}", e))?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(match stderr.lines().map(str::trim).find(|l| !l.is_empty()) {
+        Some(line) => format!("zellij {}: {}", name, line),
+        None => format!("zellij {} exited with {}", name, output.status),
+    })
+}
+
+/// Open a new tab in the current Zellij session and type a shell command
+/// into its shell (the user's); failures are recorded for `take_error`
+pub fn new_tab_with_command(tab_name: &str, cmd: &str) -> bool {
+    let typed = run(&["action", "new-tab", "--name", tab_name])
+        // The new tab's shell has focus; type the command and press Enter
+        .and_then(|_| run(&["action", "write-chars", cmd]))
+        .and_then(|_| run(&["action", "write", "13"]));
+    match typed {
+        Ok(_) => true,
+        Err(e) => {
+            record_error(e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_layout_finds_tabs_and_resolves_pane_cwds() {
+        let kdl = r#"layout {
+    cwd "/home/me"
+    tab name="api" focus=true hide_floating_panes=true {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        pane command="claude" cwd="src/api" focus=true {
+            args "--resume" "abc"
+        }
+    }
+    tab name="web" {
+        pane cwd="/srv/web"
+    }
+    new_tab_template {
+        pane cwd="ignored"
+    }
+}"#;
+        assert_eq!(parse_layout(kdl), vec![
+            LayoutTab { name: "api".to_string(), cwds: vec![PathBuf::from("/home/me/src/api")] },
+            LayoutTab { name: "web".to_string(), cwds: vec![PathBuf::from("/srv/web")] },
+        ]);
+    }
+}